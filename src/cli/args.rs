@@ -0,0 +1,290 @@
+// Libs
+use crate::{
+    schemas::{BundleFormat, PathSyntax},
+    services::DatabaseBackend,
+};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use ulid::Ulid;
+
+// Structs
+/**
+PoliWarden is a gateway that enforces security policies on the incoming requests.
+Without a command, the gateway is started.
+*/
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Args, Debug)]
+pub struct MigrateStoreArgs {
+    /**
+    The backend to read the records from.
+    */
+    #[arg(long)]
+    pub from: DatabaseBackend,
+
+    /**
+    The location of the source store. For the FileDB, its directory.
+    Defaults to the configured location.
+    */
+    #[arg(long)]
+    pub from_path: Option<PathBuf>,
+
+    /**
+    The backend to write the records to.
+    */
+    #[arg(long)]
+    pub to: DatabaseBackend,
+
+    /**
+    The location of the target store. For the FileDB, its directory.
+    Defaults to the configured location.
+    */
+    #[arg(long)]
+    pub to_path: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value_t)]
+    pub format: BundleFormat,
+
+    /**
+    Export the identities' hashed credentials.
+    Without them, the imported identities receive a new bearer.
+    */
+    #[arg(long)]
+    pub include_secrets: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ChangesArgs {
+    /**
+    The changed entity, `identity` or `policy`.
+    */
+    #[arg(long)]
+    pub entity: Option<String>,
+
+    /**
+    The id of the changed identity or policy.
+    */
+    #[arg(long)]
+    pub id: Option<String>,
+
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /**
+    The id of the last change of the previous page.
+    */
+    #[arg(long)]
+    pub cursor: Option<Ulid>,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /**
+    The bundle file.
+    */
+    pub file: PathBuf,
+
+    #[arg(long, value_enum, default_value_t)]
+    pub format: BundleFormat,
+
+    /**
+    Only report what would be imported, without writing anything.
+    */
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct IdentityCreateArgs {
+    #[arg(long)]
+    pub name: String,
+
+    /**
+    The host the identity's requests are forwarded to.
+    */
+    #[arg(long)]
+    pub host: String,
+
+    /**
+    The PEM file of the identity's certificate.
+    */
+    #[arg(long)]
+    pub certificate_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct IdentityUpdateArgs {
+    pub id: String,
+
+    /**
+    The revision the update is based on. It's rejected if the identity was updated since.
+    */
+    #[arg(long)]
+    pub revision: u64,
+
+    /**
+    The certificate is only replaced if a file is given, and the bearer is kept.
+    */
+    #[command(flatten)]
+    pub identity: IdentityCreateArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct PolicyAddArgs {
+    /**
+    The id of the identity the policy applies to.
+    */
+    #[arg(long)]
+    pub identity: String,
+
+    /**
+    The pattern of the allowed paths.
+    */
+    #[arg(long)]
+    pub path: String,
+
+    /**
+    The syntax of the path.
+    */
+    #[arg(long, value_enum, default_value_t)]
+    pub path_syntax: PathSyntax,
+
+    /**
+    The regex pattern of the allowed methods.
+    */
+    #[arg(long)]
+    pub method: String,
+
+    /**
+    The id of the policy's owner. Defaults to the PoliWarden's identity.
+    */
+    #[arg(long)]
+    pub owner: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct PolicyUpdateArgs {
+    pub id: String,
+
+    /**
+    The revision the update is based on. It's rejected if the policy was updated since.
+    */
+    #[arg(long)]
+    pub revision: u64,
+
+    #[command(flatten)]
+    pub policy: PolicyAddArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    /**
+    The id of the identity.
+    */
+    pub identity: String,
+    pub method: String,
+    pub path: String,
+}
+
+// Enums
+#[derive(Debug, Subcommand)]
+pub enum IdentityCommand {
+    /**
+    Create an identity with a generated bearer, printed once.
+    */
+    Create(IdentityCreateArgs),
+    /**
+    List the identities.
+    */
+    List,
+    /**
+    Update an identity's name, host and certificate.
+    */
+    Update(IdentityUpdateArgs),
+    /**
+    Replace an identity's bearer with a generated one, printed once.
+    */
+    RotateBearer { id: String },
+    /**
+    Delete an identity.
+    */
+    Delete { id: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PolicyCommand {
+    /**
+    Add a policy to an identity.
+    */
+    Add(PolicyAddArgs),
+    /**
+    List the policies, optionally of a single identity.
+    */
+    List {
+        #[arg(long)]
+        identity: Option<String>,
+    },
+    /**
+    Replace a policy's patterns, identity and owner.
+    */
+    Update(PolicyUpdateArgs),
+    /**
+    Remove a policy.
+    */
+    Remove { id: String },
+    /**
+    Lint the policies, exiting with an error if there's any warning.
+    */
+    Lint,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /**
+    Copy all the records from a store to another and verify them.
+    */
+    MigrateStore(MigrateStoreArgs),
+    /**
+    Print a bundle with all the identities and policies.
+    */
+    Export(ExportArgs),
+    /**
+    Import the identities and policies of a bundle, in a single transaction.
+    */
+    Import(ImportArgs),
+    /**
+    Print the change history of the identities and policies, oldest first.
+    */
+    Changes(ChangesArgs),
+    /**
+    Verify the hash chain of the audit log, exiting with an error if it's broken.
+    */
+    VerifyAudit,
+    /**
+    Print the master bearer generated on the first run, as the bearer header's value.
+    It's deleted once printed, so it can only be revealed once.
+    */
+    RevealBootstrap,
+    /**
+    Manage the identities directly in the database.
+    */
+    #[command(subcommand)]
+    Identity(IdentityCommand),
+    /**
+    Manage the policies directly in the database.
+    */
+    #[command(subcommand)]
+    Policy(PolicyCommand),
+    /**
+    Check if the identity can access the path with the method, exiting with an error if it's denied.
+    */
+    Check(CheckArgs),
+}
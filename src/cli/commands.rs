@@ -0,0 +1,286 @@
+// Libs
+use super::{
+    ChangesArgs, CheckArgs, ExportArgs, IdentityCommand, IdentityCreateArgs, IdentityUpdateArgs,
+    ImportArgs, MigrateStoreArgs, PolicyAddArgs, PolicyCommand,
+};
+use crate::{
+    schemas::{
+        Bundle, ChangeQuery, Decision, ExportOptions, Identity, IdentityIn, ImportOptions,
+        PolicyIn, QueryOptions,
+    },
+    services::{
+        authorization::{encode_bearer, AuthorizationService},
+        bundle::BundleService,
+        identity::IdentityService,
+        policy::PolicyService,
+        AuditService, BootstrapService, ChangeService, DatabaseService, LintService, Repository,
+        StoreMigrationService,
+    },
+    utils::gracefully_shutdown,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::{fmt::Display, fs::read_to_string, path::PathBuf};
+use ulid::Ulid;
+
+// Data
+/**
+The actor of the changes made with the CLI, as recorded in the change history.
+*/
+const CLI_ACTOR: &str = "cli";
+
+// Functions
+/**
+Copy the records between the stores and print the report.
+It exits with an error if the target doesn't match the source.
+*/
+pub async fn migrate_store(args: MigrateStoreArgs) {
+    let from_path = DatabaseService::resolve_location(args.from, args.from_path);
+    let to_path = DatabaseService::resolve_location(args.to, args.to_path);
+    if args.from == args.to && from_path == to_path {
+        gracefully_shutdown("The source and target stores must be different.");
+    }
+
+    let source = DatabaseService::create_database(args.from, Some(from_path));
+    let target = DatabaseService::create_database(args.to, Some(to_path));
+    let report = match StoreMigrationService::migrate(source.as_ref(), target.as_ref()).await {
+        Ok(report) => report,
+        Err(e) => gracefully_shutdown(e),
+    };
+
+    print_json(&report);
+    if !report.is_consistent() {
+        gracefully_shutdown("The target store doesn't match the source store.");
+    }
+}
+
+/**
+Print the bundle of the identities and policies, in the requested format.
+*/
+pub async fn export(args: ExportArgs) {
+    init_database().await;
+    let db = or_shutdown(DatabaseService::get_database());
+    let options = ExportOptions {
+        include_secrets: args.include_secrets,
+    };
+
+    let bundle = or_shutdown(BundleService::export(db.read().await.as_ref(), &options).await);
+    println!("{}", or_shutdown(bundle.serialize(args.format)));
+}
+
+/**
+Import the bundle file and print the report.
+*/
+pub async fn import(args: ImportArgs) {
+    let content = read_to_string(&args.file)
+        .map_err(|e| format!("Couldn't read the bundle {}: {e}", args.file.display()));
+    let bundle = or_shutdown(Bundle::parse(&or_shutdown(content), args.format));
+
+    init_database().await;
+    let db = or_shutdown(DatabaseService::get_database());
+    let options = ImportOptions {
+        dry_run: args.dry_run,
+    };
+    let db = db.read().await;
+    let report = BundleService::import(db.as_ref(), bundle, &options, CLI_ACTOR).await;
+    print_json(&or_shutdown(report));
+}
+
+/**
+Print the changes matching the filters.
+*/
+pub async fn changes(args: ChangesArgs) {
+    init_database().await;
+    let query = ChangeQuery {
+        entity: args.entity,
+        id: args.id,
+        limit: args.limit,
+        cursor: args.cursor,
+    };
+    print_json(&or_shutdown(ChangeService::get_all(&query).await));
+}
+
+/**
+Verify the audit chain of the audit file against its head and print the result.
+*/
+pub fn verify_audit() {
+    let verification = match AuditService::verify() {
+        Ok(verification) => verification,
+        Err(e) => gracefully_shutdown(e),
+    };
+
+    print_json(&verification);
+    if !verification.is_valid() {
+        gracefully_shutdown("The audit chain is broken.");
+    }
+}
+
+/**
+Print the bootstrap bearer to the standard output, never to the logs.
+*/
+pub fn reveal_bootstrap() {
+    match BootstrapService::reveal() {
+        Ok(bearer) => println!("{}", bearer.expose()),
+        Err(e) => gracefully_shutdown(e),
+    }
+}
+
+/**
+Manage the identities directly in the database.
+The created identity's bearer, and the rotated ones, are printed once, as the bearer header's value.
+*/
+pub async fn identity(command: IdentityCommand) {
+    init_database().await;
+    match command {
+        IdentityCommand::Create(args) => create_identity(args).await,
+        IdentityCommand::List => print_json(&or_shutdown(
+            IdentityService::get_all(&QueryOptions::new()).await,
+        )),
+        IdentityCommand::Update(args) => update_identity(args).await,
+        IdentityCommand::RotateBearer { id } => rotate_bearer(&id).await,
+        IdentityCommand::Delete { id } => {
+            or_shutdown(IdentityService::delete(&id, CLI_ACTOR).await)
+        }
+    }
+}
+
+/**
+Manage the policies directly in the database.
+*/
+pub async fn policy(command: PolicyCommand) {
+    init_database().await;
+    match command {
+        PolicyCommand::Add(args) => {
+            let policy = policy_in(args).await;
+            print_json(&or_shutdown(PolicyService::create(policy, CLI_ACTOR).await))
+        }
+        PolicyCommand::List { identity } => {
+            let options = match identity {
+                Some(identity_id) => QueryOptions::new().filter("identity_id", &identity_id),
+                None => QueryOptions::new(),
+            };
+            print_json(&or_shutdown(PolicyService::get_all(&options).await))
+        }
+        PolicyCommand::Update(args) => {
+            let policy = policy_in(args.policy).await;
+            let policy = PolicyService::update(&args.id, policy, args.revision, CLI_ACTOR).await;
+            print_json(&or_shutdown(policy))
+        }
+        PolicyCommand::Remove { id } => or_shutdown(PolicyService::delete(&id, CLI_ACTOR).await),
+        PolicyCommand::Lint => lint_policies().await,
+    }
+}
+
+/**
+Print the decision for the identity's request, without recording it in the metrics.
+*/
+pub async fn check(args: CheckArgs) {
+    init_database().await;
+    let db = or_shutdown(DatabaseService::get_database());
+    let db = db.read().await;
+    let db = db.as_ref();
+
+    let Some(identity) = or_shutdown(Repository::<Identity>::new(db).get(&args.identity).await)
+    else {
+        gracefully_shutdown(format!("The identity {} doesn't exist.", args.identity));
+    };
+    let decision =
+        or_shutdown(AuthorizationService::decide(db, &identity, &args.method, &args.path).await);
+
+    print_json(&decision);
+    if !matches!(decision, Decision::Allow { .. }) {
+        gracefully_shutdown("The request would be denied.");
+    }
+}
+
+async fn create_identity(args: IdentityCreateArgs) {
+    let certificate = read_certificate(args.certificate_file);
+    let bearer = Ulid::new().to_string();
+    let identity = IdentityIn::new(args.name, args.host, Some(bearer.clone()), certificate);
+    let identity = or_shutdown(IdentityService::create(identity, CLI_ACTOR).await);
+
+    print_json(&json!({
+        "bearer": encode_bearer(&identity.get_id(), &bearer),
+        "identity": identity,
+    }));
+}
+
+async fn update_identity(args: IdentityUpdateArgs) {
+    let certificate = read_certificate(args.identity.certificate_file);
+    let identity = IdentityIn::new(args.identity.name, args.identity.host, None, certificate);
+    let identity = IdentityService::update(&args.id, identity, args.revision, CLI_ACTOR).await;
+    print_json(&or_shutdown(identity));
+}
+
+async fn rotate_bearer(id: &str) {
+    let bearer = Ulid::new().to_string();
+    let identity = or_shutdown(IdentityService::rotate_bearer(id, &bearer, CLI_ACTOR).await);
+
+    print_json(&json!({
+        "bearer": encode_bearer(&identity.get_id(), &bearer),
+        "identity": identity,
+    }));
+}
+
+fn read_certificate(path: Option<PathBuf>) -> Option<String> {
+    let certificate = path.map(|path| {
+        read_to_string(&path)
+            .map_err(|e| format!("Couldn't read the certificate file {}: {e}", path.display()))
+    });
+    or_shutdown(certificate.transpose())
+}
+
+/**
+Get the policy of the arguments, checking its identities exist.
+*/
+async fn policy_in(args: PolicyAddArgs) -> PolicyIn {
+    or_shutdown(IdentityService::get(&args.identity).await);
+    let owner_id = match args.owner {
+        Some(owner_id) => or_shutdown(IdentityService::get(&owner_id).await).get_id(),
+        None => {
+            let options = QueryOptions::new().filter("name", "poliwarden").limit(1);
+            match or_shutdown(IdentityService::get_all(&options).await).first() {
+                Some(owner) => owner.get_id(),
+                None => gracefully_shutdown("The PoliWarden's identity doesn't exist."),
+            }
+        }
+    };
+
+    PolicyIn::new(
+        args.path,
+        args.path_syntax,
+        args.method,
+        owner_id,
+        args.identity,
+    )
+}
+
+async fn lint_policies() {
+    let db = or_shutdown(DatabaseService::get_database());
+    let warnings = or_shutdown(LintService::lint_all(db.read().await.as_ref()).await);
+
+    print_json(&warnings);
+    if !warnings.is_empty() {
+        gracefully_shutdown(format!("Found {} policy warnings.", warnings.len()));
+    }
+}
+
+/**
+Initialize the configured database, as the gateway does on start.
+*/
+async fn init_database() {
+    if let Err(e) = DatabaseService::init_db().await {
+        gracefully_shutdown(e);
+    }
+}
+
+fn print_json(value: &impl Serialize) {
+    match serde_json::to_string_pretty(value) {
+        Ok(value) => println!("{value}"),
+        Err(e) => gracefully_shutdown(e),
+    }
+}
+
+fn or_shutdown<T>(result: Result<T, impl Display>) -> T {
+    result.unwrap_or_else(|e| gracefully_shutdown(e))
+}
@@ -0,0 +1,5 @@
+pub use args::*;
+pub use commands::*;
+
+mod args;
+mod commands;
@@ -1,74 +1,370 @@
 // Libs
 use crate::{
-    errors::DBResult,
-    schemas::{Identity, Model},
-    traits::{database::Database, ModelProperties},
+    errors::{DBResult, DatabaseError},
+    schemas::{Identity, Model, QueryOptions},
+    traits::{database::Database, ModelProperties, SerdeModel},
+    utils::{decrypt_aead, encrypt_aead, EncryptionKey, FILEDB_SETTINGS},
 };
 use async_trait::async_trait;
 use std::{
-    fs::File,
-    io::{BufReader, BufWriter},
-    path::Path,
+    collections::HashMap,
+    fs::{metadata, read, remove_file, rename, DirBuilder, File, OpenOptions, TryLockError},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
 };
-use tracing::{debug, info, Level};
+use tracing::{debug, info, warn, Level};
 
 // Data
-const DATABASE_FILEPATH: &str = "./vol/";
+/**
+The tables contain credential hashes, so only the owner may read them.
+*/
+#[cfg(unix)]
+const TABLE_FILE_MODE: u32 = 0o600;
+#[cfg(unix)]
+const DATABASE_DIRECTORY_MODE: u32 = 0o700;
+
+/**
+The header of the encrypted tables. Tables without it are plaintext JSON.
+*/
+const ENCRYPTED_TABLE_HEADER: &[u8] = b"POLIWARDEN-ENC-V1\n";
+
+/**
+The file locked by the process using the directory.
+*/
+const PROCESS_LOCK_FILE: &str = ".lock";
+
+// Types
+/**
+The content of the tables before the transaction wrote them. `None` if the table didn't exist.
+*/
+type TransactionSnapshot = HashMap<String, Option<Vec<u8>>>;
 
 // Structs
 pub struct FileDatabase {
-    filepath: String,
+    filepath: PathBuf,
+    key: Option<EncryptionKey>,
+    previous_key: Option<EncryptionKey>,
+    /**
+    Held while a table is read and written back, so concurrent writes aren't lost.
+    */
+    write_lock: Mutex<()>,
+    /**
+    The snapshot of the active transaction.
+    It's best-effort: the writes of other callers during the transaction are also rolled back.
+    */
+    transaction: Mutex<Option<TransactionSnapshot>>,
+    /**
+    The locked file of the directory, held from `connect()` until the database is dropped.
+    The write lock only guards this process, so the directory is also locked against the other processes.
+    */
+    process_lock: Mutex<Option<File>>,
+    /**
+    Set by `connect_read_only()`. The tables are read as they're stored, the missing ones as empty,
+    and nothing is written.
+    */
+    read_only: AtomicBool,
 }
 
 // Implementations
 impl FileDatabase {
+    pub fn new(filepath: impl Into<PathBuf>) -> Self {
+        Self {
+            filepath: filepath.into(),
+            key: None,
+            previous_key: None,
+            write_lock: Mutex::new(()),
+            transaction: Mutex::new(None),
+            process_lock: Mutex::new(None),
+            read_only: AtomicBool::new(false),
+        }
+    }
+
+    /**
+    Encrypt the tables with the key.
+    The previous key is only used on `connect()`, to re-encrypt the tables with the current key.
+    */
+    pub fn with_encryption(
+        mut self,
+        key: Option<EncryptionKey>,
+        previous_key: Option<EncryptionKey>,
+    ) -> Self {
+        self.key = key;
+        self.previous_key = previous_key;
+        self
+    }
+
     /**
     Read a file and return the contents as a vector of models.
     */
-    fn read_file(&self, table_name: &str) -> Result<Vec<Model>, std::io::Error> {
-        let filename = self.get_table_path(table_name);
-        let reader = BufReader::new(File::open(filename)?);
-        let data: Vec<Model> = serde_json::from_reader(reader)?;
-        Ok(data)
+    fn read_file(&self, table_name: &str) -> DBResult<Vec<Model>> {
+        let read_only = self.is_read_only();
+        let table_path = self.get_table_path(table_name);
+        if read_only && !table_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = read(table_path)?;
+        let (plaintext, is_current) = self.decode_table(table_name, content)?;
+        // The tables are rotated on `connect()`, so only a read-only database may find an outdated one.
+        if !is_current && !read_only {
+            return Err(DatabaseError::Encryption(format!(
+                "The {table_name} table isn't stored with the current key."
+            )));
+        }
+
+        Ok(serde_json::from_slice(&plaintext)?)
     }
 
     /**
     Write a vector of models to a file.
+    The content is written to a temporary file first, so a failure never leaves a partial table.
+    */
+    fn write_file(&self, table_name: &str, data: Vec<Model>) -> DBResult<()> {
+        if self.is_read_only() {
+            return Err(DatabaseError::Io(io::Error::new(
+                io::ErrorKind::ReadOnlyFilesystem,
+                format!("Couldn't write the {table_name} table, the FileDB is read-only."),
+            )));
+        }
+
+        let mut content = serde_json::to_vec(&data)?;
+        if let Some(key) = &self.key {
+            let ciphertext = encrypt_aead(key, &content, table_name.as_bytes())
+                .map_err(|e| DatabaseError::Encryption(e.to_string()))?;
+            content = [ENCRYPTED_TABLE_HEADER, &ciphertext].concat();
+        }
+
+        self.snapshot_table(table_name)?;
+        self.write_raw_file(table_name, &content)
+    }
+
+    /**
+    Write the raw content of a table file.
     */
-    fn write_file(&self, table_name: &str, data: Vec<Model>) -> Result<(), std::io::Error> {
+    fn write_raw_file(&self, table_name: &str, content: &[u8]) -> DBResult<()> {
         let filename = self.get_table_path(table_name);
-        let writer = BufWriter::new(File::create(filename)?);
-        serde_json::to_writer(writer, &data)?;
+        let tmp_filename = filename.with_extension("json.tmp");
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(TABLE_FILE_MODE);
+        }
+
+        let mut file = options.open(&tmp_filename)?;
+        // The mode is only applied on creation, so leftover files are fixed here.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(TABLE_FILE_MODE))?;
+        }
+
+        file.write_all(content)?;
+        file.sync_all()?;
+        rename(tmp_filename, filename)?;
         Ok(())
     }
 
+    /**
+    Decode the content of a table file.
+    Returns the plaintext and if the table is stored as the current key expects.
+    */
+    fn decode_table(&self, table_name: &str, content: Vec<u8>) -> DBResult<(Vec<u8>, bool)> {
+        let Some(ciphertext) = content.strip_prefix(ENCRYPTED_TABLE_HEADER) else {
+            return Ok((content, self.key.is_none()));
+        };
+
+        let aad = table_name.as_bytes();
+        if let Some(key) = &self.key {
+            if let Ok(plaintext) = decrypt_aead(key, ciphertext, aad) {
+                return Ok((plaintext, true));
+            }
+        }
+        if let Some(previous_key) = &self.previous_key {
+            if let Ok(plaintext) = decrypt_aead(previous_key, ciphertext, aad) {
+                return Ok((plaintext, false));
+            }
+        }
+
+        Err(DatabaseError::Encryption(format!(
+            "Couldn't decrypt the {table_name} table with the provided keys."
+        )))
+    }
+
+    /**
+    Rewrite the models' tables that aren't stored as the current key expects.
+    Plaintext tables are encrypted, tables from the previous key are re-encrypted and,
+    if there's no current key, the tables are decrypted.
+    */
+    fn rotate_tables(&self) -> DBResult<()> {
+        let _lock = self.lock_tables();
+        for table_name in Model::get_table_names() {
+            let table_path = self.get_table_path(&table_name);
+            if !table_path.exists() {
+                continue;
+            }
+
+            let (plaintext, is_current) = self.decode_table(&table_name, read(&table_path)?)?;
+            if is_current {
+                continue;
+            }
+
+            let data: Vec<Model> = serde_json::from_slice(&plaintext)?;
+            self.write_file(&table_name, data)?;
+            info!(table = table_name, "Table rewritten with the current key.");
+        }
+
+        Ok(())
+    }
+
+    /**
+    Save the table's current content, if a transaction is active and it wasn't saved yet.
+    */
+    fn snapshot_table(&self, table_name: &str) -> DBResult<()> {
+        let mut transaction = self.lock_transaction();
+        let Some(snapshot) = transaction.as_mut() else {
+            return Ok(());
+        };
+        if snapshot.contains_key(table_name) {
+            return Ok(());
+        }
+
+        let table_path = self.get_table_path(table_name);
+        let content = match table_path.exists() {
+            true => Some(read(table_path)?),
+            false => None,
+        };
+        snapshot.insert(table_name.to_string(), content);
+        Ok(())
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    fn lock_transaction(&self) -> MutexGuard<'_, Option<TransactionSnapshot>> {
+        self.transaction
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /**
+    Lock the tables for writing.
+    The lock guards no data, so it's still usable if a previous writer panicked.
+    */
+    fn lock_tables(&self) -> MutexGuard<'_, ()> {
+        self.write_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     /**
     Get the index of a record in a table by its id.
     */
-    fn get_record_index(&self, table: &Vec<Model>, id: &str) -> Option<usize> {
-        table.iter().position(|x| x.get_id() == id.as_ref())
+    fn get_record_index(&self, table: &[Model], id: &str) -> Option<usize> {
+        table.iter().position(|x| x.get_id() == id)
     }
 
     /**
     Get the tablepath.
     */
-    fn get_table_path(&self, table_name: &str) -> String {
-        format!("{}{}.json", &self.filepath, table_name)
+    fn get_table_path(&self, table_name: &str) -> PathBuf {
+        self.filepath.join(format!("{table_name}.json"))
     }
 
     /**
-    Create the database directory.
+    Lock the directory for the process, failing if another process holds it.
+    The lock is released by the system when the process exits, even if it crashes.
+    */
+    fn lock_directory(&self) -> DBResult<()> {
+        let mut process_lock = self
+            .process_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if process_lock.is_some() {
+            return Ok(());
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(TABLE_FILE_MODE);
+        }
+        let file = options.open(self.filepath.join(PROCESS_LOCK_FILE))?;
+        match file.try_lock() {
+            Ok(()) => {
+                *process_lock = Some(file);
+                Ok(())
+            }
+            Err(TryLockError::WouldBlock) => Err(DatabaseError::Conflict(format!(
+                "The FileDB directory {} is used by another process.",
+                self.filepath.display()
+            ))),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    /**
+    Create the database directory, restricting it to the owner.
     */
     fn create_database_directory(&self) -> Result<(), std::io::Error> {
-        std::fs::create_dir_all(&self.filepath)
+        let mut builder = DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(DATABASE_DIRECTORY_MODE);
+        }
+        builder.create(&self.filepath)?;
+
+        // A mounted directory may not be owned by the application, so it's not fatal.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = std::fs::Permissions::from_mode(DATABASE_DIRECTORY_MODE);
+            if let Err(e) = std::fs::set_permissions(&self.filepath, permissions) {
+                warn!(
+                    filepath = %self.filepath.display(),
+                    error = %e,
+                    "Couldn't restrict the database directory permissions."
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl FileDatabase {
+    /**
+    Connect to a database in a temporary directory, with the models' tables.
+    The directory is deleted when it's dropped, so it must outlive the database.
+    */
+    pub async fn temporary() -> (tempfile::TempDir, Self) {
+        let directory = tempfile::tempdir().unwrap();
+        let db = Self::new(directory.path());
+        db.connect().await.unwrap();
+        for table_name in Model::get_table_names() {
+            db.create_table(&table_name).await.unwrap();
+        }
+        (directory, db)
     }
 }
 
 impl Default for FileDatabase {
     fn default() -> Self {
-        Self {
-            filepath: String::from(DATABASE_FILEPATH),
-        }
+        Self::new(&FILEDB_SETTINGS.filedb_path).with_encryption(
+            FILEDB_SETTINGS.filedb_key,
+            FILEDB_SETTINGS.filedb_previous_key,
+        )
     }
 }
 
@@ -76,14 +372,40 @@ impl Default for FileDatabase {
 impl Database for FileDatabase {
     fn get_database_name(&self) -> String {
         debug!("get_database_name() called.");
-        return String::from("filedb");
+        String::from("filedb")
     }
 
     async fn connect(&self) -> DBResult<()> {
         debug!("connect() called.");
+        self.create_database_directory()?;
+        self.lock_directory()?;
+        self.rotate_tables()?;
         Ok(())
     }
 
+    /**
+    Check the directory exists, without locking it or rotating its tables.
+    */
+    async fn connect_read_only(&self) -> DBResult<()> {
+        debug!("connect_read_only() called.");
+        self.ping().await?;
+        self.read_only.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /**
+    Check the directory exists. The tables are only created and rotated by `connect()`, on startup.
+    */
+    async fn ping(&self) -> DBResult<()> {
+        match metadata(&self.filepath)?.is_dir() {
+            true => Ok(()),
+            false => Err(DatabaseError::Io(io::Error::other(format!(
+                "{} isn't a directory.",
+                self.filepath.display()
+            )))),
+        }
+    }
+
     async fn get(&self, table_name: &str, id: &str) -> DBResult<Option<Model>> {
         let span = tracing::span!(Level::INFO, "", table = table_name, id = id);
         let _enter = span.enter();
@@ -93,16 +415,16 @@ impl Database for FileDatabase {
         let model_index = self.get_record_index(&table, id);
         let result = model_index.map(|index| table[index].clone());
 
-        info!(result = ?result, "Got model by id.");
+        info!(found = result.is_some(), "Got model by id.");
         Ok(result)
     }
 
-    async fn get_all(&self, table_name: &str) -> DBResult<Vec<Model>> {
+    async fn get_all(&self, table_name: &str, options: &QueryOptions) -> DBResult<Vec<Model>> {
         let span = tracing::span!(Level::INFO, "", table = table_name);
         let _enter = span.enter();
-        info!("Getting all models...");
+        info!(options = ?options, "Getting all models...");
 
-        let result = self.read_file(&table_name)?;
+        let result = options.apply(self.read_file(table_name)?)?;
 
         info!(result_size = result.len(), "Got all models.");
         Ok(result)
@@ -113,28 +435,57 @@ impl Database for FileDatabase {
         let _enter = span.enter();
         info!("Inserting model...");
 
-        let mut table: Vec<Model> = self.read_file(&table_name)?;
+        let _lock = self.lock_tables();
+        let mut table: Vec<Model> = self.read_file(table_name)?;
         table.push(data.clone());
-        self.write_file(&table_name, table)?;
+        self.write_file(table_name, table)?;
 
         info!("Inserted model.");
         Ok(())
     }
 
-    async fn update(&self, table_name: &str, data: &Model) -> DBResult<Option<()>> {
+    async fn insert_many(&self, table_name: &str, data: &[Model]) -> DBResult<()> {
+        let span = tracing::span!(Level::INFO, "", table = table_name);
+        let _enter = span.enter();
+        info!(amount = data.len(), "Inserting models...");
+
+        let _lock = self.lock_tables();
+        let mut table: Vec<Model> = self.read_file(table_name)?;
+        table.extend_from_slice(data);
+        self.write_file(table_name, table)?;
+
+        info!("Inserted models.");
+        Ok(())
+    }
+
+    async fn update(
+        &self,
+        table_name: &str,
+        data: &Model,
+        expected_revision: Option<u64>,
+    ) -> DBResult<Option<Model>> {
         let span = tracing::span!(Level::INFO, "", table = table_name, id = data.get_id());
         let _enter = span.enter();
         info!("Updating model by id...");
 
-        let mut table: Vec<Model> = self.read_file(&table_name)?;
-        match self.get_record_index(&table, &data.get_id()) {
-            None => return Ok(None),
-            Some(record_index) => table[record_index] = data.clone(),
+        let _lock = self.lock_tables();
+        let mut table: Vec<Model> = self.read_file(table_name)?;
+        let Some(record_index) = self.get_record_index(&table, &data.get_id()) else {
+            return Ok(None);
         };
-        self.write_file(&table_name, table)?;
 
-        info!("Updated model by id.");
-        Ok(Some(()))
+        let stored_revision = table[record_index].get_revision();
+        if expected_revision.is_some_and(|revision| revision != stored_revision) {
+            return Err(DatabaseError::StaleRevision(stored_revision));
+        }
+
+        let mut record = data.clone();
+        record.set_revision(stored_revision + 1);
+        table[record_index] = record.clone();
+        self.write_file(table_name, table)?;
+
+        info!(revision = record.get_revision(), "Updated model by id.");
+        Ok(Some(record))
     }
 
     async fn delete(&self, table_name: &str, id: &str) -> DBResult<Option<()>> {
@@ -142,43 +493,245 @@ impl Database for FileDatabase {
         let _enter = span.enter();
         info!("Deleting model by id...");
 
-        let mut table: Vec<Model> = self.read_file(&table_name)?;
+        let _lock = self.lock_tables();
+        let mut table: Vec<Model> = self.read_file(table_name)?;
         match self.get_record_index(&table, id) {
             None => return Ok(None),
             Some(record_index) => table.swap_remove(record_index),
         };
-        self.write_file(&table_name, table)?;
+        self.write_file(table_name, table)?;
 
         info!("Deleted model by id.");
         Ok(Some(()))
     }
 
-    async fn query(&self, query: &str) -> DBResult<Vec<Model>> {
-        // The table_name'll be passed as a query.
+    async fn create_table(&self, table_name: &str) -> DBResult<()> {
+        let span = tracing::span!(Level::INFO, "", table = table_name);
+        let _enter = span.enter();
 
+        let _lock = self.lock_tables();
         self.create_database_directory()?;
-        let table_path = self.get_table_path(query);
-        if Path::new(&table_path).exists() {
-            unreachable!("Bad logic. Query must not be called beyond migrations.");
+        if self.get_table_path(table_name).exists() {
+            debug!("Table already exists.");
+            return Ok(());
+        }
+
+        self.write_file(table_name, Vec::new())?;
+
+        info!("Created table.");
+        Ok(())
+    }
+
+    async fn begin(&self) -> DBResult<()> {
+        let mut transaction = self.lock_transaction();
+        if transaction.is_some() {
+            return Err(DatabaseError::Transaction(String::from(
+                "A transaction is already active.",
+            )));
         }
 
-        self.write_file(query, Vec::new())?;
+        *transaction = Some(TransactionSnapshot::new());
+        debug!("Transaction started.");
+        Ok(())
+    }
 
-        Ok(vec![])
+    async fn commit(&self) -> DBResult<()> {
+        match self.lock_transaction().take() {
+            None => Err(DatabaseError::Transaction(String::from(
+                "There's no active transaction.",
+            ))),
+            Some(_) => {
+                debug!("Transaction committed.");
+                Ok(())
+            }
+        }
+    }
+
+    async fn rollback(&self) -> DBResult<()> {
+        let _lock = self.lock_tables();
+        let Some(snapshot) = self.lock_transaction().take() else {
+            return Err(DatabaseError::Transaction(String::from(
+                "There's no active transaction.",
+            )));
+        };
+
+        for (table_name, content) in snapshot {
+            match content {
+                Some(content) => self.write_raw_file(&table_name, &content)?,
+                None => remove_file(self.get_table_path(&table_name))?,
+            }
+        }
+
+        info!("Transaction rolled back.");
+        Ok(())
     }
 
     async fn is_first_run(&self) -> DBResult<bool> {
         info!("Checking if it's the application's first run...");
 
-        let identity_model = Model::Identity(Identity::default());
-        let table_path = self.get_table_path(&identity_model.get_table_name());
-        let result = !Path::new(&table_path).exists();
+        let table_path = self.get_table_path(&Identity::table_name());
+        let result = !table_path.exists();
 
         info!(
-            table_path = &table_path,
+            table_path = %table_path.display(),
             is_first_run = result,
             "First run checked."
         );
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{IdentityIn, Policy};
+    use tempfile::TempDir;
+
+    const KEY: EncryptionKey = [1; 32];
+    const PREVIOUS_KEY: EncryptionKey = [2; 32];
+
+    fn identity(name: &str) -> Model {
+        let identity = IdentityIn::new(name.to_string(), String::from("a.local"), None, None);
+        Model::Identity(Identity::try_from(identity).unwrap())
+    }
+
+    /**
+    Connect to the directory's tables with the keys, creating the identity table.
+    */
+    async fn connect(
+        directory: &TempDir,
+        key: Option<EncryptionKey>,
+        previous_key: Option<EncryptionKey>,
+    ) -> DBResult<FileDatabase> {
+        let db = FileDatabase::new(directory.path()).with_encryption(key, previous_key);
+        db.connect().await?;
+        db.create_table(&Identity::table_name()).await?;
+        Ok(db)
+    }
+
+    async fn names(db: &FileDatabase) -> DBResult<Vec<String>> {
+        let models = db
+            .get_all(&Identity::table_name(), &QueryOptions::new())
+            .await?;
+        Ok(models
+            .iter()
+            .map(|model| model.get_fields()["name"].as_str().unwrap().to_string())
+            .collect())
+    }
+
+    fn table_content(directory: &TempDir) -> Vec<u8> {
+        read(directory.path().join("identity.json")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn encrypts_the_tables_with_the_key() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = connect(&directory, Some(KEY), None).await.unwrap();
+        db.insert(&Identity::table_name(), &identity("billing"))
+            .await
+            .unwrap();
+
+        let content = table_content(&directory);
+        assert!(content.starts_with(ENCRYPTED_TABLE_HEADER));
+        assert!(!String::from_utf8_lossy(&content).contains("billing"));
+        assert_eq!(names(&db).await.unwrap(), ["billing"]);
+    }
+
+    #[tokio::test]
+    async fn fails_to_read_with_the_wrong_key() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = connect(&directory, Some(KEY), None).await.unwrap();
+        db.insert(&Identity::table_name(), &identity("billing"))
+            .await
+            .unwrap();
+        drop(db);
+
+        let result = connect(&directory, Some([3; 32]), None).await;
+        assert!(matches!(result, Err(DatabaseError::Encryption(_))));
+        let result = connect(&directory, None, None).await;
+        assert!(matches!(result, Err(DatabaseError::Encryption(_))));
+    }
+
+    #[tokio::test]
+    async fn rotates_the_tables_to_the_current_key() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = connect(&directory, None, None).await.unwrap();
+        db.insert(&Identity::table_name(), &identity("billing"))
+            .await
+            .unwrap();
+        drop(db);
+
+        // The plaintext tables are encrypted, then re-encrypted from the previous key.
+        let db = connect(&directory, Some(PREVIOUS_KEY), None).await.unwrap();
+        drop(db);
+        let previous_content = table_content(&directory);
+        let db = connect(&directory, Some(KEY), Some(PREVIOUS_KEY))
+            .await
+            .unwrap();
+        assert_ne!(table_content(&directory), previous_content);
+        assert_eq!(names(&db).await.unwrap(), ["billing"]);
+        drop(db);
+
+        // Once rotated, the previous key isn't needed, and the tables are decrypted without a key.
+        let db = connect(&directory, Some(KEY), None).await.unwrap();
+        drop(db);
+        let db = connect(&directory, None, Some(KEY)).await.unwrap();
+        assert!(!table_content(&directory).starts_with(ENCRYPTED_TABLE_HEADER));
+        assert_eq!(names(&db).await.unwrap(), ["billing"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_the_stale_revisions() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = connect(&directory, None, None).await.unwrap();
+        let table_name = Identity::table_name();
+        let identity = identity("billing");
+        db.insert(&table_name, &identity).await.unwrap();
+
+        let updated = db.update(&table_name, &identity, Some(1)).await.unwrap();
+        assert_eq!(updated.map(|model| model.get_revision()), Some(2));
+        let result = db.update(&table_name, &identity, Some(1)).await;
+        assert!(matches!(result, Err(DatabaseError::StaleRevision(2))));
+
+        let stored = db.get(&table_name, &identity.get_id()).await.unwrap();
+        assert_eq!(stored.map(|model| model.get_revision()), Some(2));
+    }
+
+    #[tokio::test]
+    async fn restores_the_snapshot_on_rollback() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = connect(&directory, None, None).await.unwrap();
+        let table_name = Identity::table_name();
+        db.insert(&table_name, &identity("billing")).await.unwrap();
+        let content = table_content(&directory);
+
+        db.begin().await.unwrap();
+        db.insert(&table_name, &identity("orders")).await.unwrap();
+        db.create_table(&Policy::table_name()).await.unwrap();
+        db.rollback().await.unwrap();
+
+        assert_eq!(table_content(&directory), content);
+        assert!(!directory.path().join("policy.json").exists());
+        assert!(matches!(
+            db.rollback().await,
+            Err(DatabaseError::Transaction(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_writes_on_commit() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = connect(&directory, None, None).await.unwrap();
+        let table_name = Identity::table_name();
+
+        db.begin().await.unwrap();
+        db.insert(&table_name, &identity("billing")).await.unwrap();
+        assert!(matches!(
+            db.begin().await,
+            Err(DatabaseError::Transaction(_))
+        ));
+        db.commit().await.unwrap();
+
+        assert_eq!(names(&db).await.unwrap(), ["billing"]);
+    }
+}
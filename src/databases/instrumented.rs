@@ -0,0 +1,150 @@
+// Libs
+use crate::{
+    errors::DBResult,
+    schemas::{Model, QueryOptions},
+    services::MetricsService,
+    traits::Database,
+};
+use async_trait::async_trait;
+use std::{future::Future, time::Instant};
+use tracing::{info_span, Instrument};
+
+// Structs
+/**
+A database wrapper recording the latency of the table operations in the metrics and their spans.
+*/
+pub struct InstrumentedDatabase {
+    inner: Box<dyn Database>,
+    backend: String,
+}
+
+// Implementations
+impl InstrumentedDatabase {
+    pub fn new(inner: Box<dyn Database>) -> Self {
+        let backend = inner.get_database_name();
+        Self { inner, backend }
+    }
+
+    async fn observe<T>(
+        &self,
+        table_name: &str,
+        operation: &str,
+        future: impl Future<Output = DBResult<T>>,
+    ) -> DBResult<T> {
+        let span = info_span!(
+            "database",
+            backend = self.backend,
+            table = table_name,
+            operation
+        );
+        let start = Instant::now();
+        let result = future.instrument(span).await;
+        MetricsService::observe_database(&self.backend, table_name, operation, start.elapsed());
+        result
+    }
+}
+
+#[async_trait]
+impl Database for InstrumentedDatabase {
+    fn get_database_name(&self) -> String {
+        self.backend.clone()
+    }
+
+    async fn connect(&self) -> DBResult<()> {
+        self.inner.connect().await
+    }
+
+    async fn connect_read_only(&self) -> DBResult<()> {
+        self.inner.connect_read_only().await
+    }
+
+    async fn ping(&self) -> DBResult<()> {
+        self.inner.ping().await
+    }
+
+    async fn get(&self, table_name: &str, id: &str) -> DBResult<Option<Model>> {
+        self.observe(table_name, "get", self.inner.get(table_name, id))
+            .await
+    }
+
+    async fn get_all(&self, table_name: &str, options: &QueryOptions) -> DBResult<Vec<Model>> {
+        self.observe(
+            table_name,
+            "get_all",
+            self.inner.get_all(table_name, options),
+        )
+        .await
+    }
+
+    async fn insert(&self, table_name: &str, data: &Model) -> DBResult<()> {
+        self.observe(table_name, "insert", self.inner.insert(table_name, data))
+            .await
+    }
+
+    async fn insert_many(&self, table_name: &str, data: &[Model]) -> DBResult<()> {
+        self.observe(
+            table_name,
+            "insert_many",
+            self.inner.insert_many(table_name, data),
+        )
+        .await
+    }
+
+    async fn update(
+        &self,
+        table_name: &str,
+        data: &Model,
+        expected_revision: Option<u64>,
+    ) -> DBResult<Option<Model>> {
+        self.observe(
+            table_name,
+            "update",
+            self.inner.update(table_name, data, expected_revision),
+        )
+        .await
+    }
+
+    async fn delete(&self, table_name: &str, id: &str) -> DBResult<Option<()>> {
+        self.observe(table_name, "delete", self.inner.delete(table_name, id))
+            .await
+    }
+
+    async fn create_table(&self, table_name: &str) -> DBResult<()> {
+        self.observe(
+            table_name,
+            "create_table",
+            self.inner.create_table(table_name),
+        )
+        .await
+    }
+
+    async fn find_by(&self, table_name: &str, field: &str, value: &str) -> DBResult<Vec<Model>> {
+        self.observe(
+            table_name,
+            "find_by",
+            self.inner.find_by(table_name, field, value),
+        )
+        .await
+    }
+
+    async fn count(&self, table_name: &str, options: &QueryOptions) -> DBResult<usize> {
+        self.observe(table_name, "count", self.inner.count(table_name, options))
+            .await
+    }
+
+    async fn begin(&self) -> DBResult<()> {
+        self.inner.begin().await
+    }
+
+    async fn commit(&self) -> DBResult<()> {
+        self.inner.commit().await
+    }
+
+    async fn rollback(&self) -> DBResult<()> {
+        self.inner.rollback().await
+    }
+
+    async fn is_first_run(&self) -> DBResult<bool> {
+        self.inner.is_first_run().await
+    }
+}
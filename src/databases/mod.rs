@@ -1,3 +1,5 @@
 pub use file::*;
+pub use instrumented::*;
 
 mod file;
+mod instrumented;
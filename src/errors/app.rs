@@ -0,0 +1,179 @@
+// Libs
+use super::DatabaseError;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt::Display};
+
+// Data
+/**
+The content type of the problem details responses.
+*/
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+// Types
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+// Enums
+/**
+The application level errors. Each one has a stable code and HTTP status, so clients can handle them.
+*/
+#[derive(Debug)]
+pub enum AppError {
+    /**
+    The request has no valid credentials.
+    */
+    Unauthenticated(String),
+    /**
+    The identity has no policy allowing the request.
+    */
+    PolicyDenied(String),
+    Validation(String),
+    NotFound(String),
+    Conflict(String),
+    /**
+    The record was updated since the revision the client has seen.
+    */
+    PreconditionFailed(String),
+    Storage(DatabaseError),
+    /**
+    The upstream service failed or couldn't be reached.
+    */
+    Upstream(String),
+    Internal(String),
+}
+
+// Structs
+/**
+The JSON problem details of an error, as defined by the RFC 7807.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /**
+    The stable error code, as an extension member.
+    */
+    pub code: String,
+}
+
+// Implementations
+impl AppError {
+    /**
+    Get the stable, machine readable code of the error.
+    */
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Unauthenticated(_) => "unauthenticated",
+            AppError::PolicyDenied(_) => "policy_denied",
+            AppError::Validation(_) => "validation_failed",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::PreconditionFailed(_) => "precondition_failed",
+            AppError::Storage(_) => "storage_error",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /**
+    Get the HTTP status of the error.
+    */
+    pub fn status(&self) -> u16 {
+        match self {
+            AppError::Unauthenticated(_) => 401,
+            AppError::PolicyDenied(_) => 403,
+            AppError::Validation(_) => 400,
+            AppError::NotFound(_) => 404,
+            AppError::Conflict(_) => 409,
+            AppError::PreconditionFailed(_) => 412,
+            AppError::Storage(_) => 500,
+            AppError::Upstream(_) => 502,
+            AppError::Internal(_) => 500,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AppError::Unauthenticated(_) => "Unauthenticated",
+            AppError::PolicyDenied(_) => "Policy denied",
+            AppError::Validation(_) => "Validation failed",
+            AppError::NotFound(_) => "Not found",
+            AppError::Conflict(_) => "Conflict",
+            AppError::PreconditionFailed(_) => "Precondition failed",
+            AppError::Storage(_) => "Storage error",
+            AppError::Upstream(_) => "Upstream error",
+            AppError::Internal(_) => "Internal error",
+        }
+    }
+
+    /**
+    Get the problem details of the error, for the request's path.
+    The storage and internal errors aren't detailed, so their internals are only logged.
+    */
+    pub fn to_problem_details(&self, instance: Option<&str>) -> ProblemDetails {
+        let detail = match self {
+            AppError::Storage(_) | AppError::Internal(_) => {
+                String::from("The request couldn't be processed.")
+            }
+            AppError::Unauthenticated(detail)
+            | AppError::PolicyDenied(detail)
+            | AppError::Validation(detail)
+            | AppError::NotFound(detail)
+            | AppError::Conflict(detail)
+            | AppError::PreconditionFailed(detail)
+            | AppError::Upstream(detail) => detail.clone(),
+        };
+
+        ProblemDetails {
+            problem_type: format!("urn:poliwarden:error:{}", self.code()),
+            title: self.title().to_string(),
+            status: self.status(),
+            detail,
+            instance: instance.map(str::to_string),
+            code: self.code().to_string(),
+        }
+    }
+}
+
+// Traits
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AppError::Storage(e) => write!(f, "{}: {}", self.title(), e),
+            AppError::Unauthenticated(e)
+            | AppError::PolicyDenied(e)
+            | AppError::Validation(e)
+            | AppError::NotFound(e)
+            | AppError::Conflict(e)
+            | AppError::PreconditionFailed(e)
+            | AppError::Upstream(e)
+            | AppError::Internal(e) => write!(f, "{}: {}", self.title(), e),
+        }
+    }
+}
+
+impl From<DatabaseError> for AppError {
+    fn from(e: DatabaseError) -> Self {
+        match e {
+            DatabaseError::InvalidField(e) => AppError::Validation(e),
+            DatabaseError::Conflict(e) => AppError::Conflict(e),
+            DatabaseError::StaleRevision(revision) => AppError::PreconditionFailed(format!(
+                "The record was updated, it's at revision {revision}."
+            )),
+            e => AppError::Storage(e),
+        }
+    }
+}
@@ -13,14 +13,41 @@ pub enum DatabaseError {
     Io(std::io::Error),
     Json(serde_json::Error),
 
+    /**
+    A field of the request is invalid. It's the client's error.
+    */
     InvalidField(String),
+    /**
+    The stored or generated data is invalid. Unlike `InvalidField`, it's an internal error.
+    */
+    InvalidData(String),
+    Encryption(String),
+    UnexpectedModel(String),
+    Conflict(String),
+    /**
+    The record was updated since the expected revision. It holds the stored revision.
+    */
+    StaleRevision(u64),
+    Transaction(String),
+    Uninitialized,
 
     #[cfg(feature = "surreal")]
     Surreal(surrealdb::Error),
 }
 
 // Traits
-impl Error for DatabaseError {}
+impl Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DatabaseError::Io(e) => Some(e),
+            DatabaseError::Json(e) => Some(e),
+
+            #[cfg(feature = "surreal")]
+            DatabaseError::Surreal(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl Display for DatabaseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -28,6 +55,18 @@ impl Display for DatabaseError {
             DatabaseError::Io(e) => write!(f, "IO error: {}", e),
             DatabaseError::Json(e) => write!(f, "JSON error: {}", e),
             DatabaseError::InvalidField(e) => write!(f, "Invalid field: {}", e),
+            DatabaseError::InvalidData(e) => write!(f, "Invalid data: {}", e),
+            DatabaseError::Encryption(e) => write!(f, "Encryption error: {}", e),
+            DatabaseError::UnexpectedModel(e) => write!(f, "Unexpected model: {}", e),
+            DatabaseError::Conflict(e) => write!(f, "Conflict: {}", e),
+            DatabaseError::StaleRevision(revision) => {
+                write!(f, "Stale revision: the record is at revision {}", revision)
+            }
+            DatabaseError::Transaction(e) => write!(f, "Transaction error: {}", e),
+            DatabaseError::Uninitialized => write!(f, "The database wasn't initialized."),
+
+            #[cfg(feature = "surreal")]
+            DatabaseError::Surreal(e) => write!(f, "SurrealDB error: {}", e),
         }
     }
 }
@@ -44,9 +83,18 @@ impl From<serde_json::Error> for DatabaseError {
     }
 }
 
+impl From<argon2::Error> for DatabaseError {
+    fn from(e: argon2::Error) -> Self {
+        DatabaseError::Encryption(format!("Couldn't hash the credential: {e}"))
+    }
+}
+
+/**
+The ULIDs are only decoded from the stored records, so an invalid one is an internal error.
+*/
 impl From<DecodeError> for DatabaseError {
     fn from(_e: DecodeError) -> Self {
-        DatabaseError::InvalidField(String::from("Provided ULID is invalid."))
+        DatabaseError::InvalidData(String::from("The stored ULID is invalid."))
     }
 }
 
@@ -57,9 +105,7 @@ mod surreal {
 
     impl From<Error> for DatabaseError {
         fn from(e: Error) -> Self {
-            DatabaseError {
-                message: e.to_string(),
-            }
+            DatabaseError::Surreal(e)
         }
     }
 }
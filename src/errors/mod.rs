@@ -1,3 +1,5 @@
+pub use app::*;
 pub use database::*;
 
+mod app;
 mod database;
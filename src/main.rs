@@ -1,11 +1,20 @@
 // Libs
-use services::DatabaseService;
+use clap::Parser;
+use cli::{Cli, Command};
+use servers::{AdminServer, GatewayServer};
+use services::{AuditService, DatabaseService, ReloadService};
 use tracing::info;
-use utils::utils::{gracefully_shutdown, setup_logger};
+use utils::{
+    shutdown_telemetry,
+    utils::{gracefully_shutdown, setup_logger},
+    Settings,
+};
 
+mod cli;
 mod databases;
 mod errors;
 mod schemas;
+mod servers;
 mod services;
 mod traits;
 mod utils;
@@ -13,11 +22,56 @@ mod utils;
 // Functions
 #[tokio::main]
 async fn main() {
-    setup_logger();
+    let command = Cli::parse().command;
+    setup_logger(command.is_some());
+    Settings::init();
 
-    if let Err(e) = DatabaseService::init_db().await {
-        gracefully_shutdown(e);
+    match command {
+        None => start().await,
+        Some(Command::MigrateStore(args)) => cli::migrate_store(args).await,
+        Some(Command::Export(args)) => cli::export(args).await,
+        Some(Command::Import(args)) => cli::import(args).await,
+        Some(Command::Changes(args)) => cli::changes(args).await,
+        Some(Command::VerifyAudit) => cli::verify_audit(),
+        Some(Command::RevealBootstrap) => cli::reveal_bootstrap(),
+        Some(Command::Identity(command)) => cli::identity(command).await,
+        Some(Command::Policy(command)) => cli::policy(command).await,
+        Some(Command::Check(args)) => cli::check(args).await,
     }
+}
+
+/**
+Start the gateway.
+The admin server starts with the database initialization, so the probes can tell it isn't ready yet.
+The gateway only listens once the database is initialized.
+On shutdown, the queued audit records are written before exiting.
+*/
+async fn start() {
+    let reload_service = ReloadService::new();
+    let gateway = async {
+        if let Err(e) = DatabaseService::init_db().await {
+            gracefully_shutdown(e);
+        }
 
-    info!("Hello, world!");
+        tokio::select! {
+            _ = reload_service.watch() => {}
+            result = GatewayServer::serve() => {
+                if let Err(e) = result {
+                    gracefully_shutdown(format!("The gateway failed: {e}"));
+                }
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = gateway => {}
+        result = AdminServer::serve() => {
+            if let Err(e) = result {
+                gracefully_shutdown(format!("The admin server failed: {e}"));
+            }
+        }
+        _ = tokio::signal::ctrl_c() => info!("Shutting down..."),
+    }
+    AuditService::flush().await;
+    shutdown_telemetry();
 }
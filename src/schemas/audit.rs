@@ -0,0 +1,166 @@
+// Libs
+use super::{AccessRequest, Decision};
+use crate::utils::{hash_sha256, hmac_sha256, verify_hmac_sha256, EncryptionKey};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+// Data
+/**
+The previous hash of the first record of the chain.
+*/
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Structs
+/**
+An authorization decision in the audit log.
+The records are chained by their hashes, so a deleted or modified record breaks the chain.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AuditRecord {
+    id: Ulid,
+    /**
+    The position of the record in the chain, starting at 1.
+    */
+    sequence: u64,
+    identity_id: Option<String>,
+    method: String,
+    path: String,
+    policy_id: Option<String>,
+    decision: String,
+    /**
+    The status of the upstream response. `None` if the request wasn't forwarded.
+    */
+    upstream_status: Option<u16>,
+    previous_hash: String,
+    /**
+    The SHA-256 of the record's other fields, including the previous hash.
+    */
+    hash: String,
+    created_at: DateTime<Utc>,
+}
+
+/**
+The last record of the audit log, stored next to it.
+The records can't be deleted from the end of the log without the head noticing it,
+and, when signed, the head can't be rewritten without the audit key.
+*/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AuditHead {
+    pub sequence: u64,
+    pub hash: String,
+    /**
+    The HMAC-SHA256 of the sequence and hash, with the audit key. `None` without the key.
+    */
+    pub mac: Option<String>,
+}
+
+/**
+The result of verifying the audit chain.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AuditVerification {
+    pub records: u64,
+    /**
+    The hash of the last valid record.
+    */
+    pub last_hash: Option<String>,
+    /**
+    The sequence where the chain breaks, if it does.
+    */
+    pub broken_at: Option<u64>,
+    pub reason: Option<String>,
+}
+
+// Implementations
+impl AuditRecord {
+    /**
+    Create the record of a decision, chained after the previous record.
+    */
+    pub fn new(
+        request: &AccessRequest,
+        decision: &Decision,
+        upstream_status: Option<u16>,
+        sequence: u64,
+        previous_hash: &str,
+    ) -> serde_json::Result<Self> {
+        let mut record = Self {
+            id: Ulid::new(),
+            sequence,
+            identity_id: decision.get_identity_id().map(str::to_string),
+            method: request.method.clone(),
+            path: request.path.clone(),
+            policy_id: decision.get_policy_id().map(str::to_string),
+            decision: decision.name().to_string(),
+            upstream_status,
+            previous_hash: previous_hash.to_string(),
+            hash: String::new(),
+            created_at: Utc::now(),
+        };
+        record.hash = record.compute_hash()?;
+        Ok(record)
+    }
+
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn get_previous_hash(&self) -> &str {
+        &self.previous_hash
+    }
+
+    pub fn get_hash(&self) -> &str {
+        &self.hash
+    }
+
+    /**
+    Compute the hash of the record, as serialized without its hash.
+    */
+    pub fn compute_hash(&self) -> serde_json::Result<String> {
+        let content = serde_json::to_vec(&Self {
+            hash: String::new(),
+            ..self.clone()
+        })?;
+        Ok(hash_sha256(&content))
+    }
+}
+
+impl AuditHead {
+    /**
+    Create the head of a record, signed if there's a key.
+    */
+    pub fn new(record: &AuditRecord, key: Option<&EncryptionKey>) -> Self {
+        let mut head = Self {
+            sequence: record.get_sequence(),
+            hash: record.get_hash().to_string(),
+            mac: None,
+        };
+        head.mac = key.map(|key| hmac_sha256(key, head.signed_content().as_bytes()));
+        head
+    }
+
+    /**
+    Check the head's signature. Without a key, it can't be checked, so it's trusted.
+    With a key, an unsigned head is rejected, so the signature can't just be removed.
+    */
+    pub fn is_authentic(&self, key: Option<&EncryptionKey>) -> bool {
+        match (key, &self.mac) {
+            (None, _) => true,
+            (Some(key), Some(mac)) => {
+                verify_hmac_sha256(key, self.signed_content().as_bytes(), mac)
+            }
+            (Some(_), None) => false,
+        }
+    }
+
+    fn signed_content(&self) -> String {
+        format!("{}:{}", self.sequence, self.hash)
+    }
+}
+
+impl AuditVerification {
+    pub fn is_valid(&self) -> bool {
+        self.broken_at.is_none()
+    }
+}
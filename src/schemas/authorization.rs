@@ -0,0 +1,79 @@
+// Libs
+use super::Identity;
+use crate::utils::Secret;
+use serde::{Deserialize, Serialize};
+
+// Enums
+/**
+The result of authorizing a request.
+*/
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "decision")]
+pub enum Decision {
+    Allow {
+        identity_id: String,
+        policy_id: String,
+    },
+    Deny {
+        identity_id: String,
+    },
+    Unauthenticated,
+    /**
+    The method isn't a known HTTP method, and they're rejected.
+    */
+    UnknownMethod,
+}
+
+// Structs
+/**
+The parts of an incoming request used to authorize it.
+*/
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccessRequest {
+    pub method: String,
+    pub path: String,
+    /**
+    The value of the bearer header: `base64("IDENTITY_ID:IDENTITY_BEARER")`.
+    */
+    pub bearer: Option<Secret<String>>,
+}
+
+/**
+The decision of a request, with its authenticated identity.
+*/
+#[derive(Clone, Debug)]
+pub struct Authorization {
+    pub decision: Decision,
+    pub identity: Option<Identity>,
+}
+
+// Implementations
+impl Decision {
+    /**
+    Get the name of the decision, as used in the metrics.
+    */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Decision::Allow { .. } => "allow",
+            Decision::Deny { .. } => "deny",
+            Decision::Unauthenticated => "unauthenticated",
+            Decision::UnknownMethod => "unknown_method",
+        }
+    }
+
+    pub fn get_identity_id(&self) -> Option<&str> {
+        match self {
+            Decision::Allow { identity_id, .. } | Decision::Deny { identity_id } => {
+                Some(identity_id)
+            }
+            Decision::Unauthenticated | Decision::UnknownMethod => None,
+        }
+    }
+
+    pub fn get_policy_id(&self) -> Option<&str> {
+        match self {
+            Decision::Allow { policy_id, .. } => Some(policy_id),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// Libs
+use super::{Identity, PathSyntax, Policy};
+use crate::{
+    errors::{DBResult, DatabaseError},
+    traits::ModelProperties,
+    utils::Secret,
+};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+};
+
+// Data
+/**
+The version of the bundles exported by this application.
+*/
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Enums
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+// Structs
+/**
+A portable copy of the identities and policies, used to move them between environments.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub identities: Vec<BundleIdentity>,
+    pub policies: Vec<BundlePolicy>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BundleIdentity {
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    /**
+    Only exported on request. Without it, a new bearer is generated on import.
+    */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<IdentitySecrets>,
+}
+
+/**
+The hashed credentials of an identity. The plain credentials are never stored.
+They're redacted when formatted for the logs.
+*/
+#[derive(Clone, Deserialize, Serialize)]
+pub struct IdentitySecrets {
+    pub bearer: Option<String>,
+    pub certificate: Option<String>,
+    pub salt: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BundlePolicy {
+    pub id: String,
+    pub path: String,
+    /**
+    The bundles exported before the path syntax was added have regex paths.
+    */
+    #[serde(default)]
+    pub path_syntax: PathSyntax,
+    pub method: String,
+    pub owner_id: String,
+    pub identity_id: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ExportOptions {
+    /**
+    Export the identities' hashed credentials.
+    */
+    pub include_secrets: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ImportOptions {
+    /**
+    Only report what would be imported, without writing anything.
+    */
+    pub dry_run: bool,
+}
+
+/**
+The result of an import.
+The bundle's identities are matched to the existing ones by name. The unmatched are created.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created_identities: usize,
+    pub matched_identities: usize,
+    pub created_policies: usize,
+    /**
+    Policies skipped because an equal policy already exists.
+    */
+    pub skipped_policies: usize,
+    /**
+    The bundle's identity ids mapped to the ids in the database.
+    */
+    pub identity_ids: BTreeMap<String, String>,
+}
+
+// Implementations
+impl Bundle {
+    pub fn new(identities: Vec<BundleIdentity>, policies: Vec<BundlePolicy>) -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            exported_at: Utc::now(),
+            identities,
+            policies,
+        }
+    }
+
+    /**
+    Parse a bundle, checking if its version is supported.
+    */
+    pub fn parse(content: &str, format: BundleFormat) -> DBResult<Self> {
+        let bundle: Bundle = match format {
+            BundleFormat::Json => serde_json::from_str(content)?,
+            BundleFormat::Yaml => serde_yaml::from_str(content)
+                .map_err(|e| DatabaseError::InvalidField(e.to_string()))?,
+        };
+
+        if bundle.version != BUNDLE_VERSION {
+            return Err(DatabaseError::InvalidField(format!(
+                "The bundle version {} isn't supported.",
+                bundle.version
+            )));
+        }
+
+        Ok(bundle)
+    }
+
+    pub fn serialize(&self, format: BundleFormat) -> DBResult<String> {
+        match format {
+            BundleFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            BundleFormat::Yaml => {
+                serde_yaml::to_string(self).map_err(|e| DatabaseError::InvalidData(e.to_string()))
+            }
+        }
+    }
+}
+
+impl BundleIdentity {
+    pub fn new(identity: &Identity, include_secrets: bool) -> Self {
+        Self {
+            id: identity.get_id(),
+            name: identity.get_name().to_string(),
+            host: identity.get_host().to_string(),
+            secrets: include_secrets.then(|| identity.get_secrets()),
+        }
+    }
+}
+
+impl Debug for IdentitySecrets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentitySecrets")
+            .field("bearer", &self.bearer.as_ref().map(Secret::new))
+            .field("certificate", &self.certificate.as_ref().map(Secret::new))
+            .field("salt", &Secret::new(&self.salt))
+            .finish()
+    }
+}
+
+impl From<&Policy> for BundlePolicy {
+    fn from(policy: &Policy) -> Self {
+        Self {
+            id: policy.get_id(),
+            path: policy.get_path().to_string(),
+            path_syntax: policy.get_path_syntax(),
+            method: policy.get_method().to_string(),
+            owner_id: policy.get_owner_id().to_string(),
+            identity_id: policy.get_identity_id().to_string(),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// Libs
+use crate::traits::{ModelProperties, SerdeModel, Tracked};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ulid::Ulid;
+
+// Enums
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+// Structs
+/**
+A management change of an identity or policy, with the snapshots before and after it.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChangeRecord {
+    id: Ulid,
+    /**
+    The table name of the changed entity, such as `policy`.
+    */
+    entity: String,
+    entity_id: String,
+    action: ChangeAction,
+    /**
+    The id of the acting identity, or the declarative source for the reconciled records.
+    */
+    actor: String,
+    before: Option<Value>,
+    after: Option<Value>,
+    created_at: DateTime<Utc>,
+}
+
+/**
+The filters of the change history, as passed to the `changes` command.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct ChangeQuery {
+    pub entity: Option<String>,
+    pub id: Option<String>,
+    pub limit: Option<usize>,
+    pub cursor: Option<Ulid>,
+}
+
+// Implementations
+impl ChangeRecord {
+    /**
+    Create the record of a change. The entity is the one after the change, or before it if it was deleted.
+    */
+    pub fn new<T: Tracked>(
+        actor: &str,
+        action: ChangeAction,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> serde_json::Result<Self> {
+        let entity_id = after.or(before).map(T::get_id).unwrap_or_default();
+        Ok(Self {
+            id: Ulid::new(),
+            entity: T::table_name(),
+            entity_id,
+            action,
+            actor: actor.to_string(),
+            before: before.map(T::snapshot).transpose()?,
+            after: after.map(T::snapshot).transpose()?,
+            created_at: Utc::now(),
+        })
+    }
+
+    pub fn get_entity(&self) -> &str {
+        &self.entity
+    }
+
+    pub fn get_entity_id(&self) -> &str {
+        &self.entity_id
+    }
+
+    pub fn get_action(&self) -> ChangeAction {
+        self.action
+    }
+}
+
+impl ModelProperties for ChangeRecord {
+    fn get_table_name(&self) -> String {
+        Self::table_name()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.to_string()
+    }
+
+    /**
+    The records are append-only, so they're never updated.
+    */
+    fn get_revision(&self) -> u64 {
+        1
+    }
+
+    fn set_revision(&mut self, _revision: u64) {}
+}
+
+impl SerdeModel for ChangeRecord {
+    fn table_name() -> String {
+        String::from("change")
+    }
+}
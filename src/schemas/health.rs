@@ -0,0 +1,65 @@
+// Libs
+use serde::{Deserialize, Serialize};
+
+// Enums
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Pass,
+    Fail,
+}
+
+// Structs
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/**
+The result of the health checks. It only passes if every check passes.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+// Implementations
+impl HealthCheck {
+    /**
+    Create a check from its result. The detail is the error when it fails.
+    */
+    pub fn new(name: &str, result: Result<Option<String>, String>) -> Self {
+        let (status, detail) = match result {
+            Ok(detail) => (HealthStatus::Pass, detail),
+            Err(e) => (HealthStatus::Fail, Some(e)),
+        };
+
+        Self {
+            name: name.to_string(),
+            status,
+            detail,
+        }
+    }
+}
+
+impl HealthReport {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        let status = match checks
+            .iter()
+            .all(|check| check.status == HealthStatus::Pass)
+        {
+            true => HealthStatus::Pass,
+            false => HealthStatus::Fail,
+        };
+
+        Self { status, checks }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Pass
+    }
+}
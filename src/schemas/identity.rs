@@ -1,11 +1,21 @@
 // Libs
-use crate::utils::{generate_salt, hash_argon2};
+use super::IdentitySecrets;
+use crate::{
+    errors::{DBResult, DatabaseError},
+    traits::{ModelProperties, SerdeModel, Tracked},
+    utils::{generate_salt, hash_argon2, Secret},
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{self, Debug, Formatter};
 use ulid::Ulid;
 
 // Structs
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+/**
+An identity with its hashed credentials. They're redacted when it's formatted for the logs.
+*/
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct Identity {
     id: Ulid,
     name: String,
@@ -13,11 +23,21 @@ pub struct Identity {
     bearer: Option<String>,
     certificate: Option<String>,
     salt: String,
+    /**
+    Incremented on every update, so concurrent updates can be detected.
+    */
+    #[serde(default)]
+    revision: u64,
+    /**
+    The declarative source managing the identity, if any.
+    */
+    #[serde(default)]
+    managed_by: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct IdentityIn {
     name: String,
     host: String,
@@ -30,41 +50,47 @@ pub struct IdentityOut {
     id: Ulid,
     name: String,
     host: String,
+    revision: u64,
+    managed_by: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
 // Implementations
-impl From<IdentityIn> for Identity {
-    fn from(mut identity: IdentityIn) -> Self {
+impl TryFrom<IdentityIn> for Identity {
+    type Error = DatabaseError;
+
+    fn try_from(mut identity: IdentityIn) -> DBResult<Self> {
         let dt = Utc::now();
         let salt = generate_salt();
         if let Some(certificate) = &identity.certificate {
-            let hash = hash_argon2(&certificate, &salt);
+            let hash = hash_argon2(certificate, &salt)?;
             identity.certificate = Some(hash);
         }
 
         match &identity.bearer {
             Some(bearer) => {
-                let hash = hash_argon2(&bearer, &salt);
+                let hash = hash_argon2(bearer, &salt)?;
                 identity.bearer = Some(hash);
             }
             None => {
-                let hash = hash_argon2(&Ulid::new().to_string(), &salt);
+                let hash = hash_argon2(&Ulid::new().to_string(), &salt)?;
                 identity.bearer = Some(hash);
             }
         }
 
-        Self {
+        Ok(Self {
             id: Ulid::new(),
             name: identity.name,
             host: identity.host,
             bearer: identity.bearer,
             certificate: identity.certificate,
             salt: salt.to_string(),
+            revision: 1,
+            managed_by: None,
             created_at: dt,
             updated_at: dt,
-        }
+        })
     }
 }
 
@@ -74,6 +100,8 @@ impl From<Identity> for IdentityOut {
             id: identity.id,
             name: identity.name,
             host: identity.host,
+            revision: identity.revision,
+            managed_by: identity.managed_by,
             created_at: identity.created_at,
             updated_at: identity.updated_at,
         }
@@ -97,11 +125,140 @@ impl IdentityIn {
 }
 
 impl Identity {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn get_managed_by(&self) -> Option<&str> {
+        self.managed_by.as_deref()
+    }
+
+    /**
+    Mark the identity as managed by a declarative source.
+    */
+    pub fn set_managed_by(&mut self, source: &str) {
+        self.managed_by = Some(source.to_string());
+    }
+
+    /**
+    Get the hashed credentials of the identity.
+    */
+    pub fn get_secrets(&self) -> IdentitySecrets {
+        IdentitySecrets {
+            bearer: self.bearer.clone(),
+            certificate: self.certificate.clone(),
+            salt: self.salt.clone(),
+        }
+    }
+
+    /**
+    Replace the identity's credentials with already hashed ones.
+    */
+    pub fn with_secrets(mut self, secrets: IdentitySecrets) -> Self {
+        self.bearer = secrets.bearer;
+        self.certificate = secrets.certificate;
+        self.salt = secrets.salt;
+        self
+    }
+
+    /**
+    Replace the identity's bearer, hashing it with the identity's salt.
+    */
+    pub fn set_bearer(&mut self, bearer: &str) -> DBResult<()> {
+        self.bearer = Some(hash_argon2(bearer, &self.salt)?);
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /**
+    Update the identity with the input's fields.
+    The bearer and the certificate are only replaced when they're defined.
+    */
+    pub fn update_from(&mut self, identity: IdentityIn) -> DBResult<()> {
+        self.name = identity.name;
+        self.host = identity.host;
+        if let Some(bearer) = &identity.bearer {
+            self.bearer = Some(hash_argon2(bearer, &self.salt)?);
+        }
+        if let Some(certificate) = &identity.certificate {
+            self.certificate = Some(hash_argon2(certificate, &self.salt)?);
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+}
+
+impl IdentityOut {
     pub fn get_id(&self) -> String {
         self.id.to_string()
     }
 }
 
+impl Debug for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Identity")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .field("bearer", &self.bearer.as_ref().map(Secret::new))
+            .field("certificate", &self.certificate.as_ref().map(Secret::new))
+            .field("salt", &Secret::new(&self.salt))
+            .field("revision", &self.revision)
+            .field("managed_by", &self.managed_by)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .finish()
+    }
+}
+
+impl Debug for IdentityIn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentityIn")
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .field("bearer", &self.bearer.as_ref().map(Secret::new))
+            .field("certificate", &self.certificate.as_ref().map(Secret::new))
+            .finish()
+    }
+}
+
+impl ModelProperties for Identity {
+    fn get_table_name(&self) -> String {
+        Self::table_name()
+    }
+
+    fn get_id(&self) -> String {
+        self.id.to_string()
+    }
+
+    fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+}
+
+impl SerdeModel for Identity {
+    fn table_name() -> String {
+        String::from("identity")
+    }
+}
+
+impl Tracked for Identity {
+    /**
+    The snapshot is the output of the identity, without its credentials.
+    */
+    fn snapshot(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(IdentityOut::from(self.clone()))
+    }
+}
+
 #[cfg(feature = "surreal")]
 pub mod surreal {
     // Libs
@@ -123,10 +280,12 @@ pub mod surreal {
     }
 
     // Implementations
-    impl From<Identity> for SurrealIdentity {
-        fn from(identity: Identity) -> Self {
-            Self {
-                id: Thing::from_str(&identity.id).unwrap(),
+    impl TryFrom<Identity> for SurrealIdentity {
+        type Error = DatabaseError;
+
+        fn try_from(identity: Identity) -> DBResult<Self> {
+            Ok(Self {
+                id: parse_thing(&identity.id.to_string())?,
                 name: identity.name,
                 host: identity.host,
                 bearer: identity.bearer,
@@ -134,7 +293,13 @@ pub mod surreal {
                 salt: identity.salt,
                 created_at: Datetime::from(identity.created_at),
                 updated_at: Datetime::from(identity.updated_at),
-            }
+            })
         }
     }
+
+    // Functions
+    fn parse_thing(id: &str) -> DBResult<Thing> {
+        Thing::from_str(id)
+            .map_err(|_| DatabaseError::InvalidData(format!("Invalid record id: {id}")))
+    }
 }
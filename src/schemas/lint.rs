@@ -0,0 +1,61 @@
+// Libs
+use super::PolicyOut;
+use serde::{Deserialize, Serialize};
+
+// Enums
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /**
+    The pattern isn't a valid regex, so the policy never matches.
+    */
+    InvalidPattern,
+    /**
+    The pattern has no `^` or `$`, so it also matches the values containing it.
+    */
+    Unanchored,
+    /**
+    The pattern matches any path or method.
+    */
+    Broad,
+    /**
+    Another policy of the identity has the same path and method.
+    */
+    Duplicate,
+    /**
+    Another policy of the identity allows any path with every method the policy allows.
+    The policies overlapping only partially, or on a narrower path, aren't detected.
+    */
+    ShadowedByCatchAll,
+    MissingIdentity,
+    UnknownMethod,
+}
+
+// Structs
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LintWarning {
+    pub policy_id: String,
+    pub rule: LintRule,
+    pub message: String,
+}
+
+/**
+A created policy with the linter's warnings. They never prevent the creation.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LintedPolicy {
+    #[serde(flatten)]
+    pub policy: PolicyOut,
+    pub warnings: Vec<LintWarning>,
+}
+
+// Implementations
+impl LintWarning {
+    pub fn new(policy_id: &str, rule: LintRule, message: String) -> Self {
+        Self {
+            policy_id: policy_id.to_string(),
+            rule,
+            message,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// Libs
+use serde::{Deserialize, Serialize};
+
+// Structs
+/**
+The result of copying the records of a store to another.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct StoreMigrationReport {
+    pub from: String,
+    pub to: String,
+    pub tables: Vec<TableMigrationReport>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TableMigrationReport {
+    pub table: String,
+    pub source_count: usize,
+    pub target_count: usize,
+    pub copied: usize,
+    /**
+    Records skipped because the target already had their ids.
+    */
+    pub already_present: usize,
+    /**
+    Ids in the source but not in the target.
+    */
+    pub missing_ids: Vec<String>,
+    /**
+    Ids in the target but not in the source.
+    */
+    pub unexpected_ids: Vec<String>,
+}
+
+// Implementations
+impl StoreMigrationReport {
+    /**
+    Check if the target has exactly the same records as the source.
+    */
+    pub fn is_consistent(&self) -> bool {
+        self.tables.iter().all(|table| {
+            table.source_count == table.target_count
+                && table.missing_ids.is_empty()
+                && table.unexpected_ids.is_empty()
+        })
+    }
+}
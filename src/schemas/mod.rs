@@ -1,7 +1,25 @@
+pub use audit::*;
+pub use authorization::*;
+pub use bundle::*;
+pub use change::*;
+pub use health::*;
 pub use identity::*;
+pub use lint::*;
+pub use migration::*;
 pub use model::*;
 pub use policy::*;
+pub use policy_document::*;
+pub use query::*;
 
+mod audit;
+mod authorization;
+mod bundle;
+mod change;
+mod health;
 mod identity;
+mod lint;
+mod migration;
 mod model;
 mod policy;
+mod policy_document;
+mod query;
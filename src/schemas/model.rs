@@ -1,21 +1,55 @@
+// Libs
+use super::{ChangeRecord, Identity, Policy};
+use crate::{
+    errors::{DBResult, DatabaseError},
+    traits::{ModelProperties, SerdeModel},
+};
 use serde::{Deserialize, Serialize};
-
-use crate::traits::{ModelProperties, SerdeModel};
-
-use super::{Identity, Policy};
+use serde_json::{Map, Value};
 
 // Enums
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum Model {
     Policy(Policy),
     Identity(Identity),
+    Change(ChangeRecord),
+}
+
+impl Model {
+    /**
+    Get the table names of all the models.
+    */
+    pub fn get_table_names() -> Vec<String> {
+        vec![
+            Identity::table_name(),
+            Policy::table_name(),
+            ChangeRecord::table_name(),
+        ]
+    }
+
+    /**
+    Get the fields of a model, as it's serialized.
+    */
+    pub fn get_fields(&self) -> Map<String, Value> {
+        let value = match self {
+            Model::Policy(policy) => serde_json::to_value(policy),
+            Model::Identity(identity) => serde_json::to_value(identity),
+            Model::Change(record) => serde_json::to_value(record),
+        };
+
+        match value {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        }
+    }
 }
 
 impl ModelProperties for Model {
     fn get_table_name(&self) -> String {
         match self {
-            Model::Policy(_) => "policy".to_string(),
-            Model::Identity(_) => "identity".to_string(),
+            Model::Policy(policy) => policy.get_table_name(),
+            Model::Identity(identity) => identity.get_table_name(),
+            Model::Change(record) => record.get_table_name(),
         }
     }
 
@@ -23,18 +57,26 @@ impl ModelProperties for Model {
         match self {
             Model::Policy(policy) => policy.get_id(),
             Model::Identity(identity) => identity.get_id(),
+            Model::Change(record) => record.get_id(),
         }
     }
 
-    fn get_migration_script(&self, database_name: &str) -> String {
-        // let filedb_script = format!("./migrations/{}_filedb.sql", self.get_table_name());
-        match database_name {
-            _ => self.get_table_name(),
+    fn get_revision(&self) -> u64 {
+        match self {
+            Model::Policy(policy) => policy.get_revision(),
+            Model::Identity(identity) => identity.get_revision(),
+            Model::Change(record) => record.get_revision(),
         }
     }
-}
 
-impl SerdeModel for Model {}
+    fn set_revision(&mut self, revision: u64) {
+        match self {
+            Model::Policy(policy) => policy.set_revision(revision),
+            Model::Identity(identity) => identity.set_revision(revision),
+            Model::Change(record) => record.set_revision(revision),
+        }
+    }
+}
 
 impl From<Policy> for Model {
     fn from(policy: Policy) -> Self {
@@ -47,3 +89,51 @@ impl From<Identity> for Model {
         Model::Identity(identity)
     }
 }
+
+impl TryFrom<Model> for Policy {
+    type Error = DatabaseError;
+
+    fn try_from(model: Model) -> DBResult<Self> {
+        match model {
+            Model::Policy(policy) => Ok(policy),
+            model => Err(unexpected_model(&model, &Policy::table_name())),
+        }
+    }
+}
+
+impl TryFrom<Model> for Identity {
+    type Error = DatabaseError;
+
+    fn try_from(model: Model) -> DBResult<Self> {
+        match model {
+            Model::Identity(identity) => Ok(identity),
+            model => Err(unexpected_model(&model, &Identity::table_name())),
+        }
+    }
+}
+
+impl From<ChangeRecord> for Model {
+    fn from(record: ChangeRecord) -> Self {
+        Model::Change(record)
+    }
+}
+
+impl TryFrom<Model> for ChangeRecord {
+    type Error = DatabaseError;
+
+    fn try_from(model: Model) -> DBResult<Self> {
+        match model {
+            Model::Change(record) => Ok(record),
+            model => Err(unexpected_model(&model, &ChangeRecord::table_name())),
+        }
+    }
+}
+
+// Functions
+fn unexpected_model(model: &Model, expected_table: &str) -> DatabaseError {
+    DatabaseError::UnexpectedModel(format!(
+        "Expected a {} record, got a {} record.",
+        expected_table,
+        model.get_table_name()
+    ))
+}
@@ -1,16 +1,67 @@
 // Libs
+use crate::traits::{ModelProperties, SerdeModel, Tracked};
 use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use ulid::Ulid;
 
+// Data
+/**
+The HTTP methods known by the application.
+*/
+pub const HTTP_METHODS: [&str; 9] = [
+    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH",
+];
+
+// Enums
+/**
+The syntax of the policies' paths. They're all compiled to an anchored regex, except the regex itself.
+*/
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PathSyntax {
+    #[default]
+    Regex,
+    /**
+    `*` matches a segment's characters, `**` matches any characters and `?` matches one character.
+    */
+    Glob,
+    /**
+    `{name}` matches a whole segment.
+    */
+    Template,
+}
+
 // Structs
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Policy {
     id: Ulid,
+    /**
+    The stable name of the policy. Only the managed policies have it.
+    */
+    #[serde(default)]
+    name: Option<String>,
     path: String,
+    /**
+    The records created before the path syntax was added are regexes.
+    */
+    #[serde(default)]
+    path_syntax: PathSyntax,
     method: String,
     owner_id: String,
     identity_id: String,
+    /**
+    Incremented on every update, so concurrent updates can be detected.
+    */
+    #[serde(default)]
+    revision: u64,
+    /**
+    The declarative source managing the policy, if any.
+    */
+    #[serde(default)]
+    managed_by: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -18,6 +69,8 @@ pub struct Policy {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PolicyIn {
     path: String,
+    #[serde(default)]
+    path_syntax: PathSyntax,
     method: String,
     owner_id: String,
     identity_id: String,
@@ -26,34 +79,81 @@ pub struct PolicyIn {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct PolicyOut {
     id: Ulid,
+    name: Option<String>,
     path: String,
+    path_syntax: PathSyntax,
     method: String,
     owner_id: String,
     identity_id: String,
+    revision: u64,
+    managed_by: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
 // Implementations
+impl PathSyntax {
+    /**
+    Compile the path pattern to the regex matching the requests' paths.
+    */
+    pub fn compile(self, pattern: &str) -> Result<Regex, String> {
+        let regex = match self {
+            PathSyntax::Regex => pattern.to_string(),
+            PathSyntax::Glob => glob_to_regex(pattern),
+            PathSyntax::Template => template_to_regex(pattern)?,
+        };
+        Regex::new(&regex).map_err(|e| e.to_string())
+    }
+}
+
 impl From<PolicyIn> for Policy {
     fn from(policy: PolicyIn) -> Self {
         let dt = Utc::now();
         Self {
             id: Ulid::new(),
+            name: None,
             path: policy.path,
+            path_syntax: policy.path_syntax,
             method: policy.method,
             owner_id: policy.owner_id,
             identity_id: policy.identity_id,
+            revision: 1,
+            managed_by: None,
             created_at: dt,
             updated_at: dt,
         }
     }
 }
 
+impl From<Policy> for PolicyOut {
+    fn from(policy: Policy) -> Self {
+        Self {
+            id: policy.id,
+            name: policy.name,
+            path: policy.path,
+            path_syntax: policy.path_syntax,
+            method: policy.method,
+            owner_id: policy.owner_id,
+            identity_id: policy.identity_id,
+            revision: policy.revision,
+            managed_by: policy.managed_by,
+            created_at: policy.created_at,
+            updated_at: policy.updated_at,
+        }
+    }
+}
+
 impl PolicyIn {
-    pub fn new(path: String, method: String, owner_id: String, identity_id: String) -> Self {
+    pub fn new(
+        path: String,
+        path_syntax: PathSyntax,
+        method: String,
+        owner_id: String,
+        identity_id: String,
+    ) -> Self {
         Self {
             path,
+            path_syntax,
             method,
             owner_id,
             identity_id,
@@ -62,15 +162,150 @@ impl PolicyIn {
 }
 
 impl Policy {
-    pub fn get_id(&self) -> String {
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_managed_by(&self) -> Option<&str> {
+        self.managed_by.as_deref()
+    }
+
+    /**
+    Mark the policy as managed by a declarative source, with its stable name.
+    */
+    pub fn set_managed_by(&mut self, source: &str, name: &str) {
+        self.managed_by = Some(source.to_string());
+        self.name = Some(name.to_string());
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_path_syntax(&self) -> PathSyntax {
+        self.path_syntax
+    }
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn get_owner_id(&self) -> &str {
+        &self.owner_id
+    }
+
+    /**
+    Check if the policy has the same rule as the input.
+    */
+    pub fn matches(&self, policy: &PolicyIn) -> bool {
+        self.path == policy.path
+            && self.path_syntax == policy.path_syntax
+            && self.method == policy.method
+            && self.owner_id == policy.owner_id
+            && self.identity_id == policy.identity_id
+    }
+
+    pub fn get_identity_id(&self) -> &str {
+        &self.identity_id
+    }
+
+    /**
+    Update the policy with the input's fields.
+    */
+    pub fn update_from(&mut self, policy: PolicyIn) {
+        self.path = policy.path;
+        self.path_syntax = policy.path_syntax;
+        self.method = policy.method;
+        self.owner_id = policy.owner_id;
+        self.identity_id = policy.identity_id;
+        self.updated_at = Utc::now();
+    }
+}
+
+impl ModelProperties for Policy {
+    fn get_table_name(&self) -> String {
+        Self::table_name()
+    }
+
+    fn get_id(&self) -> String {
         self.id.to_string()
     }
+
+    fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+}
+
+impl SerdeModel for Policy {
+    fn table_name() -> String {
+        String::from("policy")
+    }
+}
+
+impl Tracked for Policy {
+    /**
+    The snapshot is the output of the policy.
+    */
+    fn snapshot(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(PolicyOut::from(self.clone()))
+    }
+}
+
+// Functions
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn template_to_regex(template: &str) -> Result<String, String> {
+    let mut regex = String::from("^");
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            return Err(format!("The template `{template}` has an unclosed `{{`."));
+        };
+        let name = &rest[start + 1..end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!(
+                "The template `{template}` has an invalid parameter `{{{name}}}`."
+            ));
+        }
+
+        regex.push_str(&regex::escape(&rest[..start]));
+        regex.push_str("[^/]+");
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        return Err(format!("The template `{template}` has an unopened `}}`."));
+    }
+
+    regex.push_str(&regex::escape(rest));
+    regex.push('$');
+    Ok(regex)
 }
 
 #[cfg(feature = "surreal")]
 pub mod surreal {
     // Libs
     use super::*;
+    use crate::errors::{DBResult, DatabaseError};
     use std::str::FromStr;
     use surrealdb::sql::{Datetime, Thing};
 
@@ -87,17 +322,78 @@ pub mod surreal {
     }
 
     // Implementations
-    impl From<Policy> for SurrealPolicy {
-        fn from(policy: Policy) -> Self {
-            Self {
-                id: Thing::from_str(&policy.id).unwrap(),
+    impl TryFrom<Policy> for SurrealPolicy {
+        type Error = DatabaseError;
+
+        fn try_from(policy: Policy) -> DBResult<Self> {
+            Ok(Self {
+                id: parse_thing(&policy.id.to_string())?,
                 path: policy.path,
                 method: policy.method,
-                owner_id: Thing::from_str(&policy.owner_id).unwrap(),
-                identity_id: Thing::from_str(&policy.identity_id).unwrap(),
+                owner_id: parse_thing(&policy.owner_id)?,
+                identity_id: parse_thing(&policy.identity_id)?,
                 created_at: Datetime::from(policy.created_at),
                 updated_at: Datetime::from(policy.updated_at),
-            }
+            })
+        }
+    }
+
+    // Functions
+    fn parse_thing(id: &str) -> DBResult<Thing> {
+        Thing::from_str(id)
+            .map_err(|_| DatabaseError::InvalidData(format!("Invalid record id: {id}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(syntax: PathSyntax, pattern: &str) -> Regex {
+        syntax.compile(pattern).unwrap()
+    }
+
+    #[test]
+    fn compiles_the_globs() {
+        assert_eq!(glob_to_regex("/users/*"), r"^/users/[^/]*$");
+        assert_eq!(glob_to_regex("/v?/**"), r"^/v[^/]/.*$");
+        assert_eq!(glob_to_regex("/a.b+(c)"), r"^/a\.b\+\(c\)$");
+
+        let segment = compile(PathSyntax::Glob, "/users/*");
+        assert!(segment.is_match("/users/42"));
+        assert!(segment.is_match("/users/"));
+        assert!(!segment.is_match("/users/42/orders"));
+
+        let any = compile(PathSyntax::Glob, "/users/**");
+        assert!(any.is_match("/users/42/orders"));
+        assert!(!any.is_match("/users"));
+
+        let character = compile(PathSyntax::Glob, "/v?/users");
+        assert!(character.is_match("/v1/users"));
+        assert!(!character.is_match("/v10/users"));
+        assert!(!compile(PathSyntax::Glob, "/users.json").is_match("/users-json"));
+    }
+
+    #[test]
+    fn compiles_the_templates() {
+        assert_eq!(
+            template_to_regex("/users/{id}/orders").unwrap(),
+            r"^/users/[^/]+/orders$"
+        );
+
+        let template = compile(PathSyntax::Template, "/users/{user_id}/orders/{id}");
+        assert!(template.is_match("/users/42/orders/7"));
+        assert!(!template.is_match("/users//orders/7"));
+        assert!(!template.is_match("/users/42/7/orders/7"));
+        assert!(!template.is_match("/users/42/orders/7/items"));
+        assert!(compile(PathSyntax::Template, "/a.b").is_match("/a.b"));
+        assert!(!compile(PathSyntax::Template, "/a.b").is_match("/aXb"));
+    }
+
+    #[test]
+    fn rejects_the_invalid_templates() {
+        for template in ["/users/{id", "/users/id}", "/users/{}", "/users/{user-id}"] {
+            assert!(template_to_regex(template).is_err(), "{template}");
         }
     }
 }
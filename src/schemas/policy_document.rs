@@ -0,0 +1,169 @@
+// Libs
+use super::PathSyntax;
+use crate::errors::{DBResult, DatabaseError};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs::read_to_string, path::Path};
+
+// Data
+const BUILTIN_POLICY_DOCUMENT: &str = include_str!("../assets/builtin_policies.yaml");
+
+// Enums
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PolicyDocumentFormat {
+    Yaml,
+    Toml,
+}
+
+// Structs
+/**
+A declarative description of identities and policies, reconciled into the database.
+The records are referenced by their names, so the document doesn't depend on the generated ids.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocument {
+    /**
+    Delete the records managed by the document that aren't in it anymore.
+    */
+    #[serde(default)]
+    pub prune: bool,
+    #[serde(default)]
+    pub identities: Vec<DeclaredIdentity>,
+    #[serde(default)]
+    pub policies: Vec<DeclaredPolicy>,
+}
+
+/**
+An identity declared in a policy document.
+Its credentials aren't declared, so its bearer is generated with the `identity rotate-bearer` command.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredIdentity {
+    pub name: String,
+    pub host: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredPolicy {
+    pub name: String,
+    /**
+    The name of the identity the policy is applied to.
+    */
+    pub identity: String,
+    /**
+    The name of the policy's owner identity.
+    */
+    #[serde(default = "default_owner")]
+    pub owner: String,
+    pub path: String,
+    #[serde(default)]
+    pub path_syntax: PathSyntax,
+    pub method: String,
+}
+
+/**
+The result of reconciling a policy document.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ReconcileReport {
+    pub source: String,
+    pub created_identities: usize,
+    pub updated_identities: usize,
+    pub deleted_identities: usize,
+    pub created_policies: usize,
+    pub updated_policies: usize,
+    /**
+    Existing policies, equal to a declared one, that became managed by the document.
+    */
+    pub adopted_policies: usize,
+    pub deleted_policies: usize,
+    /**
+    The names of the undeclared identities kept because they still hold or own policies.
+    */
+    pub kept_identities: Vec<String>,
+}
+
+// Implementations
+impl PolicyDocument {
+    /**
+    Get the built-in document, with the policies of the master identity.
+    */
+    pub fn builtin() -> DBResult<Self> {
+        Self::parse(BUILTIN_POLICY_DOCUMENT, PolicyDocumentFormat::Yaml)
+    }
+
+    /**
+    Load a document from a file. The format is defined by the file's extension.
+    */
+    pub fn load(path: &Path) -> DBResult<Self> {
+        let format = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml" | "yml") => PolicyDocumentFormat::Yaml,
+            Some("toml") => PolicyDocumentFormat::Toml,
+            _ => {
+                return Err(DatabaseError::InvalidField(String::from(
+                    "The policy file must be a YAML or TOML file.",
+                )))
+            }
+        };
+
+        Self::parse(&read_to_string(path)?, format)
+    }
+
+    pub fn parse(content: &str, format: PolicyDocumentFormat) -> DBResult<Self> {
+        let document: PolicyDocument = match format {
+            PolicyDocumentFormat::Yaml => serde_yaml::from_str(content)
+                .map_err(|e| DatabaseError::InvalidField(e.to_string()))?,
+            PolicyDocumentFormat::Toml => {
+                toml::from_str(content).map_err(|e| DatabaseError::InvalidField(e.to_string()))?
+            }
+        };
+
+        document.validate()?;
+        Ok(document)
+    }
+
+    /**
+    Check if the names are unique and the policies' patterns are valid regexes.
+    */
+    fn validate(&self) -> DBResult<()> {
+        let mut identity_names = HashSet::new();
+        for identity in &self.identities {
+            if !identity_names.insert(&identity.name) {
+                return Err(DatabaseError::InvalidField(format!(
+                    "The identity {} is declared more than once.",
+                    identity.name
+                )));
+            }
+        }
+
+        let mut policy_names = HashSet::new();
+        for policy in &self.policies {
+            if policy.name.is_empty() || !policy_names.insert(&policy.name) {
+                return Err(DatabaseError::InvalidField(format!(
+                    "The policy name `{}` is empty or declared more than once.",
+                    policy.name
+                )));
+            }
+
+            let path = policy.path_syntax.compile(&policy.path);
+            for pattern in [path, Regex::new(&policy.method).map_err(|e| e.to_string())] {
+                if let Err(e) = pattern {
+                    return Err(DatabaseError::InvalidField(format!(
+                        "The policy {} has an invalid pattern: {e}",
+                        policy.name
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Functions
+fn default_owner() -> String {
+    String::from("poliwarden")
+}
@@ -0,0 +1,325 @@
+// Libs
+use crate::{
+    errors::{DBResult, DatabaseError},
+    schemas::Model,
+    traits::ModelProperties,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use ulid::Ulid;
+
+// Data
+/**
+The fields holding the identities' credentials. They can't be filtered or sorted by,
+as the results would reveal their hashes.
+*/
+const SECRET_FIELDS: [&str; 3] = ["bearer", "certificate", "salt"];
+
+// Enums
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// Structs
+/**
+A filter that matches the records whose field is equal to the value.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Filter {
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Sort {
+    pub field: String,
+    pub order: SortOrder,
+}
+
+/**
+The options to query the records of a table.
+Without any option, all the records are returned sorted by their ids.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct QueryOptions {
+    /**
+    The maximum amount of records to return.
+    */
+    pub limit: Option<usize>,
+    /**
+    The id of the last record of the previous page.
+    */
+    pub cursor: Option<Ulid>,
+    pub filters: Vec<Filter>,
+    pub sort: Option<Sort>,
+}
+
+// Implementations
+impl QueryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn cursor(mut self, cursor: Ulid) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    pub fn filter(mut self, field: &str, value: &str) -> Self {
+        self.filters.push(Filter {
+            field: field.to_string(),
+            value: value.to_string(),
+        });
+        self
+    }
+
+    pub fn sort(mut self, field: &str, order: SortOrder) -> Self {
+        self.sort = Some(Sort {
+            field: field.to_string(),
+            order,
+        });
+        self
+    }
+
+    /**
+    Check if the fields are valid identifiers, so backends can safely use them in their queries.
+    The secret fields are rejected.
+    */
+    pub fn validate(&self) -> DBResult<()> {
+        let fields = self.filters.iter().map(|filter| &filter.field);
+        for field in fields.chain(self.sort.iter().map(|sort| &sort.field)) {
+            let is_identifier =
+                !field.is_empty() && field.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+            if !is_identifier || SECRET_FIELDS.contains(&field.as_str()) {
+                return Err(DatabaseError::InvalidField(format!(
+                    "The field `{field}` can't be queried."
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+    Apply the options to the records in memory.
+    It's meant to backends that can't query the records natively, such as the FileDB.
+    */
+    pub fn apply(&self, records: Vec<Model>) -> DBResult<Vec<Model>> {
+        self.validate()?;
+
+        // Each record is serialized once, keeping only its id and sort key.
+        let sort_field = self.sort.as_ref().map_or("id", |sort| &sort.field);
+        let sort_order = self.sort.as_ref().map_or(SortOrder::Asc, |sort| sort.order);
+        let mut records: Vec<(Option<Value>, String, Model)> = records
+            .into_iter()
+            .filter_map(|record| {
+                let mut fields = record.get_fields();
+                let is_match = self.filters.iter().all(|filter| {
+                    fields
+                        .get(&filter.field)
+                        .is_some_and(|value| value_to_string(value) == filter.value)
+                });
+                is_match.then(|| (fields.remove(sort_field), record.get_id(), record))
+            })
+            .collect();
+
+        records.sort_by(|(a_key, a_id, _), (b_key, b_id, _)| {
+            let ordering =
+                compare_values(a_key.as_ref(), b_key.as_ref()).then_with(|| a_id.cmp(b_id));
+            match sort_order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+
+        if let Some(cursor) = &self.cursor {
+            let cursor = cursor.to_string();
+            let start = match records.iter().position(|(_, id, _)| *id == cursor) {
+                Some(index) => index + 1,
+                // As the ids are sortable, the page can continue even if the cursor was deleted.
+                None if sort_field == "id" => {
+                    records.partition_point(|(_, id, _)| match sort_order {
+                        SortOrder::Asc => *id < cursor,
+                        SortOrder::Desc => *id > cursor,
+                    })
+                }
+                None => {
+                    return Err(DatabaseError::InvalidField(String::from(
+                        "The cursor doesn't match any record.",
+                    )))
+                }
+            };
+            records.drain(..start);
+        }
+
+        if let Some(limit) = self.limit {
+            records.truncate(limit);
+        }
+
+        Ok(records.into_iter().map(|(_, _, record)| record).collect())
+    }
+}
+
+// Functions
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/**
+Compare two field values. Missing values come first.
+*/
+fn compare_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            let (a, b) = (
+                a.as_f64().unwrap_or_default(),
+                b.as_f64().unwrap_or_default(),
+            );
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Some(Value::String(a)), Some(Value::String(b))) => {
+            // The dates can have different precisions, so they're compared as dates.
+            match (a.parse::<DateTime<Utc>>(), b.parse::<DateTime<Utc>>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            }
+        }
+        (Some(a), Some(b)) => value_to_string(a).cmp(&value_to_string(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::{PathSyntax, Policy, PolicyIn};
+
+    fn policy(method: &str, identity_id: &str) -> Model {
+        let policy: Policy = PolicyIn::new(
+            String::from("^/$"),
+            PathSyntax::Regex,
+            method.to_string(),
+            String::from("owner"),
+            identity_id.to_string(),
+        )
+        .into();
+        policy.into()
+    }
+
+    fn ids(records: &[Model]) -> Vec<String> {
+        records.iter().map(Model::get_id).collect()
+    }
+
+    #[test]
+    fn filters_the_records() {
+        let records = vec![policy("GET", "a"), policy("POST", "b"), policy("PUT", "a")];
+
+        let result = QueryOptions::new()
+            .filter("identity_id", "a")
+            .apply(records)
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert!(result
+            .iter()
+            .all(|record| record.get_fields()["identity_id"] == "a"));
+    }
+
+    #[test]
+    fn sorts_by_the_field() {
+        let records = vec![policy("GET", "a"), policy("PUT", "a"), policy("POST", "a")];
+
+        let result = QueryOptions::new()
+            .sort("method", SortOrder::Desc)
+            .apply(records)
+            .unwrap();
+
+        let methods: Vec<Value> = result
+            .iter()
+            .map(|record| record.get_fields()["method"].clone())
+            .collect();
+        assert_eq!(methods, ["PUT", "POST", "GET"]);
+    }
+
+    #[test]
+    fn paginates_by_the_cursor() {
+        let records: Vec<Model> = (0..5).map(|_| policy("GET", "a")).collect();
+        let mut expected = ids(&records);
+        expected.sort();
+
+        let first_page = QueryOptions::new().limit(2).apply(records.clone()).unwrap();
+        assert_eq!(ids(&first_page), expected[..2]);
+
+        let cursor = expected[1].parse().unwrap();
+        let second_page = QueryOptions::new()
+            .limit(2)
+            .cursor(cursor)
+            .apply(records)
+            .unwrap();
+        assert_eq!(ids(&second_page), expected[2..4]);
+    }
+
+    #[test]
+    fn continues_after_a_deleted_cursor() {
+        let mut records: Vec<Model> = (0..3).map(|_| policy("GET", "a")).collect();
+        records.sort_by_key(Model::get_id);
+        let deleted = records.remove(1);
+
+        let result = QueryOptions::new()
+            .cursor(deleted.get_id().parse().unwrap())
+            .apply(records.clone())
+            .unwrap();
+
+        assert_eq!(ids(&result), ids(&records[1..]));
+    }
+
+    #[test]
+    fn rejects_an_unknown_cursor_when_sorted_by_another_field() {
+        let records = vec![policy("GET", "a")];
+
+        let result = QueryOptions::new()
+            .sort("method", SortOrder::Asc)
+            .cursor(Ulid::new())
+            .apply(records);
+
+        assert!(matches!(result, Err(DatabaseError::InvalidField(_))));
+    }
+
+    #[test]
+    fn rejects_the_secret_fields() {
+        for field in SECRET_FIELDS {
+            let filtered = QueryOptions::new().filter(field, "hash").apply(Vec::new());
+            let sorted = QueryOptions::new()
+                .sort(field, SortOrder::Asc)
+                .apply(Vec::new());
+
+            assert!(matches!(filtered, Err(DatabaseError::InvalidField(_))));
+            assert!(matches!(sorted, Err(DatabaseError::InvalidField(_))));
+        }
+    }
+
+    #[test]
+    fn rejects_the_invalid_fields() {
+        let result = QueryOptions::new()
+            .filter("identity_id; DROP", "a")
+            .apply(Vec::new());
+
+        assert!(matches!(result, Err(DatabaseError::InvalidField(_))));
+    }
+}
@@ -0,0 +1,102 @@
+// Libs
+use crate::{
+    errors::{AppError, AppResult, PROBLEM_CONTENT_TYPE},
+    schemas::HealthReport,
+    services::{HealthService, MetricsService},
+    utils::{catch_panic, ADMIN_SETTINGS},
+};
+use axum::{
+    extract::Request,
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::{from_fn, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use std::io;
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+// Structs
+/**
+The admin server, listening on its own port, apart from the gateway's traffic.
+*/
+pub struct AdminServer;
+
+// Implementations
+impl AdminServer {
+    pub async fn serve() -> io::Result<()> {
+        let listener = TcpListener::bind(ADMIN_SETTINGS.admin_address).await?;
+        info!(address = %ADMIN_SETTINGS.admin_address, "Admin server listening.");
+        axum::serve(listener, Self::router()).await
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz))
+            .route("/metrics", get(metrics))
+            .layer(from_fn(catch_panics))
+    }
+}
+
+// Functions
+async fn healthz() -> Response {
+    health_response(HealthService::liveness())
+}
+
+async fn readyz() -> Response {
+    health_response(HealthService::readiness().await)
+}
+
+/**
+Build the response of a health report. It's `503 Service Unavailable` if any check fails.
+*/
+fn health_response(report: HealthReport) -> Response {
+    let status = match report.is_healthy() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report)).into_response()
+}
+
+async fn metrics() -> AppResult<Response> {
+    let metrics = MetricsService::export().map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(([(CONTENT_TYPE, "text/plain; version=0.0.4")], metrics).into_response())
+}
+
+/**
+Turn the handlers' panics into internal errors, instead of dropping the connection.
+*/
+pub async fn catch_panics(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    match catch_panic(async move { Ok(next.run(request).await) }).await {
+        Ok(response) => response,
+        Err(e) => problem_response(&e, Some(&path)),
+    }
+}
+
+/**
+Build the problem details response of an error. The server errors are logged, since their details aren't returned.
+*/
+pub fn problem_response(error: &AppError, instance: Option<&str>) -> Response {
+    let status = StatusCode::from_u16(error.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if status.is_server_error() {
+        error!(error = %error, code = error.code(), "Request failed.");
+    }
+
+    let problem = error.to_problem_details(instance);
+    (
+        status,
+        [(CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+        Json(problem),
+    )
+        .into_response()
+}
+
+// Traits
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        problem_response(&self, None)
+    }
+}
@@ -0,0 +1,367 @@
+// Libs
+use super::{catch_panics, problem_response};
+use crate::{
+    errors::{AppError, AppResult},
+    schemas::{AccessRequest, Decision},
+    services::{
+        authorization::AuthorizationService, AuditService, DatabaseService, MetricsService,
+    },
+    utils::{inject_trace_context, set_remote_parent, Secret, DEFAULT_SETTINGS, HTTP_SETTINGS},
+};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::HOST, Uri},
+    middleware::from_fn,
+    response::Response,
+    Router,
+};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use once_cell::sync::Lazy;
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    time::Instant,
+};
+use tokio::net::TcpListener;
+use tracing::{error, info, info_span, instrument, warn, Instrument, Span};
+
+// Data
+/**
+The client of the upstream requests. Its connections are pooled between the requests.
+*/
+static UPSTREAM_CLIENT: Lazy<Client<HttpConnector, Body>> =
+    Lazy::new(|| Client::builder(TokioExecutor::new()).build_http());
+
+// Structs
+/**
+The gateway, authorizing the requests and forwarding the allowed ones to their identities' hosts.
+*/
+pub struct GatewayServer;
+
+// Implementations
+impl GatewayServer {
+    pub async fn serve() -> io::Result<()> {
+        let address = SocketAddr::from((Ipv4Addr::UNSPECIFIED, HTTP_SETTINGS.feature_http_port));
+        let listener = TcpListener::bind(address).await?;
+        info!(%address, "Gateway listening.");
+        axum::serve(listener, Self::router()).await
+    }
+
+    fn router() -> Router {
+        Router::new().fallback(handle).layer(from_fn(catch_panics))
+    }
+}
+
+// Functions
+async fn handle(request: Request) -> Response {
+    let path = request.uri().path().to_string();
+    let span = request_span(&request);
+    match forward(request).instrument(span).await {
+        Ok(response) => response,
+        Err(e) => problem_response(&e, Some(&path)),
+    }
+}
+
+/**
+Get the span of a request, continuing the caller's trace when it sends one.
+*/
+fn request_span(request: &Request) -> Span {
+    let span = info_span!("gateway", method = %request.method());
+    set_remote_parent(&span, request.headers());
+    span
+}
+
+/**
+Authorize the request and forward it to the identity's host, auditing the decision.
+The bearer header is removed, so the credentials never reach the upstream.
+*/
+async fn forward(mut request: Request) -> AppResult<Response> {
+    let path = normalize_path(request.uri().path())?;
+    let bearer_header = DEFAULT_SETTINGS.load().bearer_header.clone();
+    let bearer = request.headers_mut().remove(&bearer_header);
+    let access_request = AccessRequest {
+        method: request.method().to_string(),
+        path,
+        bearer: bearer
+            .and_then(|bearer| bearer.to_str().ok().map(str::to_string))
+            .map(Secret::new),
+    };
+
+    let db = DatabaseService::get_database()?;
+    let authorization =
+        AuthorizationService::authorize(db.read().await.as_ref(), &access_request).await?;
+    let host = match (&authorization.decision, &authorization.identity) {
+        (Decision::Allow { .. }, Some(identity)) => identity.get_host().to_string(),
+        (decision, _) => {
+            audit(&access_request, decision, None).await;
+            return Err(rejection(decision, &access_request));
+        }
+    };
+
+    let result = send(request, &host, &access_request.path).await;
+    let upstream_status = result
+        .as_ref()
+        .ok()
+        .map(|response| response.status().as_u16());
+    audit(&access_request, &authorization.decision, upstream_status).await;
+    result
+}
+
+/**
+Send an allowed request to its host, with the trace context of its span when the telemetry is enabled.
+The path is the authorized one, so the upstream never resolves the request to another path.
+*/
+#[instrument(name = "upstream", skip_all, fields(host = host))]
+async fn send(mut request: Request, host: &str, path: &str) -> AppResult<Response> {
+    let method = request.method().to_string();
+    *request.uri_mut() = upstream_uri(host, path, request.uri().query())?;
+    request.headers_mut().remove(HOST);
+    inject_trace_context(request.headers_mut());
+    let start = Instant::now();
+    let result = UPSTREAM_CLIENT.request(request).await;
+    let status = result
+        .as_ref()
+        .ok()
+        .map(|response| response.status().as_u16());
+    MetricsService::observe_upstream(host, &method, status, start.elapsed());
+
+    match result {
+        Ok(response) => Ok(response.map(Body::new)),
+        Err(e) => {
+            warn!(host, error = %e, "Couldn't reach the upstream.");
+            Err(AppError::Upstream(format!(
+                "The upstream {host} couldn't be reached."
+            )))
+        }
+    }
+}
+
+/**
+Queue the decision for the audit log. It's written by the audit writer, so the request doesn't wait for the disk.
+The request was already answered or forwarded, so a failure is only logged.
+*/
+async fn audit(request: &AccessRequest, decision: &Decision, upstream_status: Option<u16>) {
+    if let Err(e) = AuditService::record(request, decision, upstream_status).await {
+        error!(error = %e, "Couldn't audit the decision.");
+    }
+}
+
+/**
+Get the error of a request that isn't allowed.
+*/
+fn rejection(decision: &Decision, request: &AccessRequest) -> AppError {
+    match decision {
+        Decision::Unauthenticated => {
+            AppError::Unauthenticated(String::from("The request has no valid credentials."))
+        }
+        Decision::UnknownMethod => AppError::Validation(format!(
+            "The method {} isn't a known HTTP method.",
+            request.method
+        )),
+        Decision::Allow { .. } | Decision::Deny { .. } => AppError::PolicyDenied(format!(
+            "No policy allows {} {}.",
+            request.method, request.path
+        )),
+    }
+}
+
+/**
+Get the URI of the request on the identity's host, over HTTP.
+*/
+fn upstream_uri(host: &str, path: &str, query: Option<&str>) -> AppResult<Uri> {
+    let uri = match query {
+        Some(query) => format!("http://{host}{path}?{query}"),
+        None => format!("http://{host}{path}"),
+    };
+    uri.parse()
+        .map_err(|e| AppError::Internal(format!("The host {host} isn't valid: {e}")))
+}
+
+/**
+Normalize the request's path, so the policies are evaluated on the path the upstream resolves.
+The unreserved characters are decoded and the dot-segments are resolved, as defined by RFC 3986.
+The encoded slashes are rejected, since the upstreams don't agree on whether they separate segments.
+*/
+fn normalize_path(path: &str) -> AppResult<String> {
+    if !path.starts_with('/') {
+        return Err(invalid_path(path, "isn't absolute"));
+    }
+
+    let mut decoded = String::with_capacity(path.len());
+    let mut characters = path.char_indices();
+    while let Some((index, character)) = characters.next() {
+        if character != '%' {
+            decoded.push(character);
+            continue;
+        }
+
+        let byte = path
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| invalid_path(path, "has an invalid percent-escape"))?;
+        characters.nth(1);
+        match byte {
+            b'/' | b'\\' => return Err(invalid_path(path, "has an encoded slash")),
+            byte if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                decoded.push(char::from(byte))
+            }
+            byte => decoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    Ok(remove_dot_segments(&decoded))
+}
+
+/**
+Resolve the `.` and `..` segments of an absolute path. The `..` segments never go above the root.
+*/
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let mut output: Vec<&str> = Vec::with_capacity(segments.len());
+    for (index, segment) in segments.iter().enumerate() {
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." {
+                    output.pop();
+                }
+                // A path ending with a dot-segment is a directory, so its slash is kept.
+                if index + 1 == segments.len() {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
+
+fn invalid_path(path: &str, reason: &str) -> AppError {
+    AppError::Validation(format!("The path {path} {reason}."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, http::Method};
+
+    /**
+    Start an upstream answering with the method, URI and host of the requests it receives.
+    */
+    async fn echo_upstream() -> String {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        let router = Router::new().fallback(|request: Request| async move {
+            let host = request.headers().get(HOST).cloned();
+            format!("{} {} {host:?}", request.method(), request.uri())
+        });
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        host
+    }
+
+    #[test]
+    fn resolves_the_dot_segments() {
+        assert_eq!(normalize_path("/a/../admin").unwrap(), "/admin");
+        assert_eq!(normalize_path("/a/./b/.").unwrap(), "/a/b/");
+        assert_eq!(normalize_path("/a/b/..").unwrap(), "/a/");
+        assert_eq!(normalize_path("/../../admin").unwrap(), "/admin");
+        assert_eq!(normalize_path("/orders//1").unwrap(), "/orders//1");
+    }
+
+    #[test]
+    fn decodes_the_unreserved_characters() {
+        assert_eq!(normalize_path("/a/%2e%2E/admin").unwrap(), "/admin");
+        assert_eq!(normalize_path("/%61dmin/%7Eme").unwrap(), "/admin/~me");
+        assert_eq!(
+            normalize_path("/files/a%20b%3f").unwrap(),
+            "/files/a%20b%3F"
+        );
+    }
+
+    #[test]
+    fn rejects_the_ambiguous_paths() {
+        for path in ["/a/..%2Fadmin", "/a%5C..%5Cadmin", "/a/%2", "/a/%zz", "*"] {
+            let error = normalize_path(path).unwrap_err();
+            assert!(matches!(error, AppError::Validation(_)), "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn forwards_the_authorized_path_with_the_query() {
+        let host = echo_upstream().await;
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/a/../orders?page=2")
+            .header(HOST, "gateway.local")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = send(request, &host, "/orders").await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            format!("POST /orders?page=2 Some({host:?})")
+        );
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn continues_the_callers_trace_upstream() {
+        use opentelemetry::{global, trace::TracerProvider};
+        use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = SdkTracerProvider::builder().build().tracer("test");
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        let _subscriber = tracing::subscriber::set_default(subscriber);
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        let router = Router::new().fallback(|request: Request| async move {
+            let header = |name: &str| request.headers()[name].to_str().unwrap().to_string();
+            format!("{} {}", header("traceparent"), header("tracestate"))
+        });
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let traceparent = format!("00-{trace_id}-00f067aa0ba902b7-01");
+        let request = Request::builder()
+            .uri("/orders")
+            .header("traceparent", &traceparent)
+            .header("tracestate", "vendor=value")
+            .body(Body::empty())
+            .unwrap();
+
+        let span = request_span(&request);
+        let response = send(request, &host, "/orders")
+            .instrument(span)
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let (forwarded_parent, forwarded_state) = body.split_once(' ').unwrap();
+
+        // The trace continues upstream, with the gateway's span as the parent.
+        assert!(forwarded_parent.starts_with(&format!("00-{trace_id}-")));
+        assert_ne!(forwarded_parent, traceparent);
+        assert_eq!(forwarded_state, "vendor=value");
+    }
+
+    #[tokio::test]
+    async fn fails_on_an_unreachable_upstream() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let result = send(request, &host, "/").await;
+        assert!(matches!(result, Err(AppError::Upstream(_))));
+    }
+}
@@ -0,0 +1,5 @@
+pub use admin::*;
+pub use gateway::*;
+
+mod admin;
+mod gateway;
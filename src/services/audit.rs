@@ -0,0 +1,528 @@
+// Libs
+use crate::{
+    errors::{DBResult, DatabaseError},
+    schemas::{
+        AccessRequest, AuditHead, AuditRecord, AuditVerification, Decision, AUDIT_GENESIS_HASH,
+    },
+    utils::{EncryptionKey, AUDIT_SETTINGS},
+};
+use once_cell::sync::Lazy;
+use std::{
+    ffi::OsString,
+    fs::{read_to_string, rename, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    thread::Builder,
+};
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
+use tracing::{debug, error, warn};
+
+// Data
+/**
+The queue of the audit writer, started on the first record.
+The writes block on the disk, so they're done by a thread of their own instead of the requests' tasks.
+*/
+static AUDIT_WRITER: Lazy<Sender<AuditMessage>> = Lazy::new(|| {
+    let (sender, receiver) = channel(AUDIT_QUEUE_CAPACITY);
+    let writer = Builder::new()
+        .name(String::from("audit-writer"))
+        .spawn(move || run_writer(receiver));
+    if let Err(e) = writer {
+        error!(error = %e, "Couldn't start the audit writer.");
+    }
+    sender
+});
+
+/**
+The decisions waiting to be written. The requests wait for room when it's full, so no decision is dropped.
+*/
+const AUDIT_QUEUE_CAPACITY: usize = 4096;
+
+/**
+The most records written, and flushed to the disk, at once.
+*/
+const AUDIT_BATCH_SIZE: usize = 256;
+
+/**
+The audit file contains the requests' paths, so only the owner may read it.
+*/
+#[cfg(unix)]
+const AUDIT_FILE_MODE: u32 = 0o600;
+
+// Enums
+enum AuditMessage {
+    Record {
+        request: AccessRequest,
+        decision: Decision,
+        upstream_status: Option<u16>,
+    },
+    /**
+    Answered once the records queued before it are written.
+    */
+    Flush(oneshot::Sender<()>),
+}
+
+// Structs
+/**
+Record the authorization decisions in a hash-chained audit log.
+The records are appended to the audit file, and its head is written next to it, in `<audit file>.head`.
+*/
+pub struct AuditService;
+
+// Implementations
+impl AuditService {
+    /**
+    Queue a decision, once the upstream status is known, to be written by the audit writer.
+    The writer chains the records in the order they're queued.
+    */
+    pub async fn record(
+        request: &AccessRequest,
+        decision: &Decision,
+        upstream_status: Option<u16>,
+    ) -> DBResult<()> {
+        let message = AuditMessage::Record {
+            request: AccessRequest {
+                method: request.method.clone(),
+                path: request.path.clone(),
+                bearer: None,
+            },
+            decision: decision.clone(),
+            upstream_status,
+        };
+        AUDIT_WRITER
+            .send(message)
+            .await
+            .map_err(|_| writer_stopped())
+    }
+
+    /**
+    Wait for the queued records to be written. Nothing is waited if no record was queued.
+    */
+    pub async fn flush() {
+        let Some(writer) = Lazy::get(&AUDIT_WRITER) else {
+            return;
+        };
+
+        let (sender, receiver) = oneshot::channel();
+        if writer.send(AuditMessage::Flush(sender)).await.is_err() || receiver.await.is_err() {
+            warn!("{}", writer_stopped());
+        }
+    }
+
+    /**
+    Verify the chain of the audit file against its head.
+    */
+    pub fn verify() -> DBResult<AuditVerification> {
+        let audit_file = &AUDIT_SETTINGS.audit_file;
+        let records = read_records(audit_file)?;
+        let head = read_head(&head_path(audit_file))?;
+        Ok(verify_chain(
+            &records,
+            head.as_ref(),
+            AUDIT_SETTINGS.audit_key.as_ref(),
+        ))
+    }
+}
+
+// Functions
+/**
+Write the queued records until the queue is closed.
+The queued records are written in batches, so the disk is flushed once per batch instead of once per record.
+*/
+fn run_writer(mut receiver: Receiver<AuditMessage>) {
+    let audit_file = &AUDIT_SETTINGS.audit_file;
+    let key = AUDIT_SETTINGS.audit_key.as_ref();
+    let mut head = None;
+
+    while let Some(message) = receiver.blocking_recv() {
+        let mut messages = vec![message];
+        while messages.len() < AUDIT_BATCH_SIZE {
+            match receiver.try_recv() {
+                Ok(message) => messages.push(message),
+                Err(_) => break,
+            }
+        }
+
+        let mut entries = Vec::with_capacity(messages.len());
+        let mut flushes = Vec::new();
+        for message in messages {
+            match message {
+                AuditMessage::Record {
+                    request,
+                    decision,
+                    upstream_status,
+                } => entries.push((request, decision, upstream_status)),
+                AuditMessage::Flush(flush) => flushes.push(flush),
+            }
+        }
+
+        if !entries.is_empty() {
+            if let Err(e) = write_batch(audit_file, key, &mut head, &entries) {
+                error!(error = %e, records = entries.len(), "Couldn't audit the decisions.");
+            }
+        }
+        for flush in flushes {
+            // The flush's caller may have stopped waiting, which isn't an error.
+            let _ = flush.send(());
+        }
+    }
+}
+
+/**
+Chain the decisions after the head, append them to the audit file and replace its head.
+The head is loaded from the audit file when it's unknown, and forgotten if the write fails,
+so the next batch is chained after what was actually written.
+*/
+fn write_batch(
+    audit_file: &Path,
+    key: Option<&EncryptionKey>,
+    head: &mut Option<AuditHead>,
+    entries: &[(AccessRequest, Decision, Option<u16>)],
+) -> DBResult<()> {
+    let (mut sequence, mut previous_hash) = match head.take() {
+        Some(head) => (head.sequence, head.hash),
+        None => match read_records(audit_file)?.pop() {
+            Some(record) => (record.get_sequence(), record.get_hash().to_string()),
+            None => (0, AUDIT_GENESIS_HASH.to_string()),
+        },
+    };
+
+    let mut records = Vec::with_capacity(entries.len());
+    for (request, decision, upstream_status) in entries {
+        sequence += 1;
+        let record = AuditRecord::new(
+            request,
+            decision,
+            *upstream_status,
+            sequence,
+            &previous_hash,
+        )?;
+        previous_hash = record.get_hash().to_string();
+        records.push(record);
+    }
+    let Some(last_record) = records.last() else {
+        return Ok(());
+    };
+
+    let new_head = AuditHead::new(last_record, key);
+    append_records(audit_file, &records)?;
+    write_head(&head_path(audit_file), &new_head)?;
+
+    debug!(
+        sequence = new_head.sequence,
+        records = records.len(),
+        "Decisions audited."
+    );
+    *head = Some(new_head);
+    Ok(())
+}
+
+fn writer_stopped() -> DatabaseError {
+    DatabaseError::Io(io::Error::other("The audit writer stopped."))
+}
+
+/**
+Verify the chain, stopping at the first missing, reordered or modified record.
+The last record must be the head, so the deletion of the latest records is detected too.
+*/
+fn verify_chain(
+    records: &[AuditRecord],
+    head: Option<&AuditHead>,
+    key: Option<&EncryptionKey>,
+) -> AuditVerification {
+    let mut verification = AuditVerification::default();
+    let mut previous_hash = AUDIT_GENESIS_HASH.to_string();
+
+    for record in records {
+        let expected_sequence = verification.records + 1;
+        let reason = if record.get_sequence() != expected_sequence {
+            Some(format!(
+                "Expected the record {expected_sequence}, found the record {}.",
+                record.get_sequence()
+            ))
+        } else if record.get_previous_hash() != previous_hash {
+            Some(String::from(
+                "The previous hash doesn't match the previous record.",
+            ))
+        } else if record.compute_hash().ok().as_deref() != Some(record.get_hash()) {
+            Some(String::from("The record was modified."))
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            return broken(verification, expected_sequence, reason);
+        }
+
+        previous_hash = record.get_hash().to_string();
+        verification.records += 1;
+        verification.last_hash = Some(previous_hash.clone());
+    }
+
+    let records = verification.records;
+    let (sequence, reason) = match head {
+        None if records == 0 => return verification,
+        None => (records, String::from("The audit head is missing.")),
+        Some(head) if !head.is_authentic(key) => (
+            head.sequence,
+            String::from("The audit head isn't authentic."),
+        ),
+        Some(head) if head.sequence > records => (
+            records + 1,
+            format!(
+                "The head is the record {}, the records after {records} were deleted.",
+                head.sequence
+            ),
+        ),
+        Some(head) if head.sequence < records => (
+            head.sequence + 1,
+            format!(
+                "The head is the record {}, the later records weren't written by the service.",
+                head.sequence
+            ),
+        ),
+        Some(head) if verification.last_hash.as_deref() != Some(head.hash.as_str()) => (
+            records,
+            String::from("The last record doesn't match the head."),
+        ),
+        Some(_) => return verification,
+    };
+    broken(verification, sequence, reason)
+}
+
+fn broken(mut verification: AuditVerification, sequence: u64, reason: String) -> AuditVerification {
+    warn!(sequence, reason, "The audit chain is broken.");
+    verification.broken_at = Some(sequence);
+    verification.reason = Some(reason);
+    verification
+}
+
+/**
+Get the path of the audit file's head.
+*/
+fn head_path(audit_file: &Path) -> PathBuf {
+    let mut path = OsString::from(audit_file.as_os_str());
+    path.push(".head");
+    PathBuf::from(path)
+}
+
+fn open_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(AUDIT_FILE_MODE);
+    }
+    options
+}
+
+/**
+Append the records to the audit file, as JSON lines, and flush them to the disk.
+*/
+fn append_records(audit_file: &Path, records: &[AuditRecord]) -> DBResult<()> {
+    let mut lines = Vec::new();
+    for record in records {
+        serde_json::to_writer(&mut lines, record)?;
+        lines.push(b'\n');
+    }
+
+    let mut file = open_options().append(true).create(true).open(audit_file)?;
+    file.write_all(&lines)?;
+    file.sync_data()?;
+    Ok(())
+}
+
+/**
+Replace the head, writing it to a temporary file first so it's never partially written.
+*/
+fn write_head(head_file: &Path, head: &AuditHead) -> DBResult<()> {
+    let mut temporary_file = OsString::from(head_file.as_os_str());
+    temporary_file.push(".tmp");
+
+    let mut file = open_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temporary_file)?;
+    file.write_all(&serde_json::to_vec(head)?)?;
+    file.sync_data()?;
+    rename(&temporary_file, head_file)?;
+    Ok(())
+}
+
+/**
+Read the records of the audit file. A missing file has no records.
+*/
+fn read_records(audit_file: &Path) -> DBResult<Vec<AuditRecord>> {
+    let Some(content) = read_optional(audit_file)? else {
+        return Ok(Vec::new());
+    };
+
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                DatabaseError::InvalidData(format!("The audit line {} is invalid: {e}", index + 1))
+            })
+        })
+        .collect()
+}
+
+fn read_head(head_file: &Path) -> DBResult<Option<AuditHead>> {
+    let Some(content) = read_optional(head_file)? else {
+        return Ok(None);
+    };
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| DatabaseError::InvalidData(format!("The audit head is invalid: {e}")))
+}
+
+fn read_optional(path: &Path) -> DBResult<Option<String>> {
+    match read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const KEY: EncryptionKey = [7; 32];
+
+    fn chain(length: u64) -> Vec<AuditRecord> {
+        let request = AccessRequest {
+            method: String::from("GET"),
+            path: String::from("/orders"),
+            bearer: None,
+        };
+        let decision = Decision::Allow {
+            identity_id: String::from("identity"),
+            policy_id: String::from("policy"),
+        };
+
+        let mut records: Vec<AuditRecord> = Vec::new();
+        for sequence in 1..=length {
+            let previous_hash = records
+                .last()
+                .map_or(AUDIT_GENESIS_HASH, |record| record.get_hash());
+            let record =
+                AuditRecord::new(&request, &decision, Some(200), sequence, previous_hash).unwrap();
+            records.push(record);
+        }
+        records
+    }
+
+    fn head(records: &[AuditRecord]) -> AuditHead {
+        AuditHead::new(records.last().unwrap(), Some(&KEY))
+    }
+
+    #[test]
+    fn accepts_an_intact_chain() {
+        let records = chain(3);
+        let verification = verify_chain(&records, Some(&head(&records)), Some(&KEY));
+        assert!(verification.is_valid());
+        assert_eq!(verification.records, 3);
+        assert_eq!(
+            verification.last_hash.as_deref(),
+            Some(records[2].get_hash())
+        );
+    }
+
+    #[test]
+    fn accepts_an_empty_log() {
+        assert!(verify_chain(&[], None, Some(&KEY)).is_valid());
+    }
+
+    #[test]
+    fn detects_a_modified_record() {
+        let mut records = chain(3);
+        let mut value = serde_json::to_value(&records[1]).unwrap();
+        value["path"] = Value::from("/admin");
+        records[1] = serde_json::from_value(value).unwrap();
+
+        let verification = verify_chain(&records, Some(&head(&records)), Some(&KEY));
+        assert_eq!(verification.broken_at, Some(2));
+        assert_eq!(verification.records, 1);
+    }
+
+    #[test]
+    fn detects_a_deleted_record() {
+        let mut records = chain(3);
+        let head = head(&records);
+        records.remove(1);
+        assert_eq!(
+            verify_chain(&records, Some(&head), Some(&KEY)).broken_at,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn detects_the_deleted_latest_records() {
+        let mut records = chain(3);
+        let head = head(&records);
+        records.truncate(1);
+
+        let verification = verify_chain(&records, Some(&head), Some(&KEY));
+        assert_eq!(verification.broken_at, Some(2));
+        assert_eq!(verification.records, 1);
+    }
+
+    #[test]
+    fn detects_a_missing_head() {
+        assert!(!verify_chain(&chain(2), None, Some(&KEY)).is_valid());
+    }
+
+    #[test]
+    fn chains_the_batches_after_the_written_records() {
+        let directory = tempfile::tempdir().unwrap();
+        let audit_file = directory.path().join("audit.jsonl");
+        let entry = (
+            AccessRequest {
+                method: String::from("GET"),
+                path: String::from("/orders"),
+                bearer: None,
+            },
+            Decision::Unauthenticated,
+            None,
+        );
+
+        let mut head = None;
+        write_batch(
+            &audit_file,
+            Some(&KEY),
+            &mut head,
+            &[entry.clone(), entry.clone()],
+        )
+        .unwrap();
+        assert_eq!(head.as_ref().map(|head| head.sequence), Some(2));
+        // A new writer loads the chain from the audit file.
+        let mut head = None;
+        write_batch(&audit_file, Some(&KEY), &mut head, &[entry]).unwrap();
+
+        let records = read_records(&audit_file).unwrap();
+        let head = read_head(&head_path(&audit_file)).unwrap();
+        let verification = verify_chain(&records, head.as_ref(), Some(&KEY));
+        assert!(verification.is_valid());
+        assert_eq!(verification.records, 3);
+    }
+
+    #[test]
+    fn detects_a_forged_head() {
+        let mut records = chain(3);
+        records.truncate(1);
+        let forged = AuditHead::new(&records[0], Some(&[8; 32]));
+        let unsigned = AuditHead::new(&records[0], None);
+
+        assert!(!verify_chain(&records, Some(&forged), Some(&KEY)).is_valid());
+        assert!(!verify_chain(&records, Some(&unsigned), Some(&KEY)).is_valid());
+    }
+}
@@ -0,0 +1,328 @@
+// Libs
+use super::{MetricsService, Repository};
+use crate::{
+    errors::DBResult,
+    schemas::{AccessRequest, Authorization, Decision, Identity, PathSyntax, Policy, HTTP_METHODS},
+    traits::{Database, ModelProperties},
+    utils::{hash_argon2, DEFAULT_SETTINGS},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+use subtle::ConstantTimeEq;
+use tracing::{debug, field::Empty, instrument, warn, Span};
+
+// Data
+/**
+The method pattern matching all the known HTTP methods.
+*/
+pub const ALL_METHODS: &str = "^ALL$";
+
+/**
+The compiled patterns of the policies, by their ids.
+A policy is compiled when it's first evaluated after being loaded or changed, instead of on every request.
+*/
+static COMPILED_POLICIES: Lazy<RwLock<HashMap<String, Arc<CompiledPolicy>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+// Structs
+/**
+The compiled path and method of a policy, with the patterns they were compiled from.
+An invalid pattern is `None`, so the policy never matches.
+*/
+struct CompiledPolicy {
+    path: String,
+    path_syntax: PathSyntax,
+    method: String,
+    path_regex: Option<Regex>,
+    method_regex: Option<Regex>,
+}
+
+/**
+Authenticate the requests' identities and evaluate their policies.
+*/
+pub struct AuthorizationService;
+
+// Implementations
+impl AuthorizationService {
+    /**
+    Authorize a request, recording the decision in the metrics.
+    The identity is kept, so the allowed request can be forwarded to its host.
+    */
+    #[instrument(
+        name = "authorize",
+        skip_all,
+        fields(method = request.method, path = request.path, decision = Empty)
+    )]
+    pub async fn authorize(db: &dyn Database, request: &AccessRequest) -> DBResult<Authorization> {
+        let bearer = request
+            .bearer
+            .as_ref()
+            .map(|bearer| bearer.expose().as_str());
+        let (decision, identity) = match Self::is_rejected(&request.method) {
+            true => (Decision::UnknownMethod, None),
+            false => match Self::authenticate(db, bearer).await? {
+                None => (Decision::Unauthenticated, None),
+                Some(identity) => {
+                    let decision =
+                        Self::decide(db, &identity, &request.method, &request.path).await?;
+                    (decision, Some(identity))
+                }
+            },
+        };
+
+        Span::current().record("decision", decision.name());
+        debug!(decision = decision.name(), "Request authorized.");
+        MetricsService::record_decision(&decision);
+        Ok(Authorization { decision, identity })
+    }
+
+    /**
+    Get the identity of the bearer, formatted as `base64("IDENTITY_ID:IDENTITY_BEARER")`.
+    */
+    #[instrument(name = "authenticate", skip_all, fields(identity_id = Empty))]
+    pub async fn authenticate(
+        db: &dyn Database,
+        bearer: Option<&str>,
+    ) -> DBResult<Option<Identity>> {
+        if DEFAULT_SETTINGS.load().disable_bearer {
+            return Ok(None);
+        }
+        let Some((id, secret)) = bearer.and_then(decode_bearer) else {
+            return Ok(None);
+        };
+        let Some(identity) = Repository::<Identity>::new(db).get(&id).await? else {
+            return Ok(None);
+        };
+
+        Span::current().record("identity_id", id.as_str());
+        let secrets = identity.get_secrets();
+        let hash = hash_argon2(&secret, &secrets.salt)?;
+        // The hashes are compared in constant time, so the comparison doesn't leak their prefix.
+        let is_valid = secrets
+            .bearer
+            .is_some_and(|bearer| bearer.as_bytes().ct_eq(hash.as_bytes()).into());
+        match is_valid {
+            true => Ok(Some(identity)),
+            false => Ok(None),
+        }
+    }
+
+    /**
+    Decide if the authenticated identity can access the path with the method.
+    */
+    pub async fn decide(
+        db: &dyn Database,
+        identity: &Identity,
+        method: &str,
+        path: &str,
+    ) -> DBResult<Decision> {
+        if Self::is_rejected(method) {
+            return Ok(Decision::UnknownMethod);
+        }
+
+        Ok(match Self::evaluate(db, identity, method, path).await? {
+            Some(policy) => Decision::Allow {
+                identity_id: identity.get_id(),
+                policy_id: policy.get_id(),
+            },
+            None => Decision::Deny {
+                identity_id: identity.get_id(),
+            },
+        })
+    }
+
+    /**
+    Check if the method is rejected, for not being a known HTTP method.
+    They're only rejected if `POLIWARDEN_REJECT_UNKNOWN_METHODS` is enabled.
+    */
+    pub fn is_rejected(method: &str) -> bool {
+        DEFAULT_SETTINGS.load().reject_unknown_methods
+            && !HTTP_METHODS.contains(&method.to_ascii_uppercase().as_str())
+    }
+
+    /**
+    Get the first identity's policy matching the method and path.
+    The methods are matched case-insensitively.
+    */
+    #[instrument(name = "evaluate", skip_all, fields(identity_id = identity.get_id()))]
+    pub async fn evaluate(
+        db: &dyn Database,
+        identity: &Identity,
+        method: &str,
+        path: &str,
+    ) -> DBResult<Option<Policy>> {
+        let policies = Repository::<Policy>::new(db)
+            .find_by("identity_id", &identity.get_id())
+            .await?;
+
+        let method = method.to_ascii_uppercase();
+        Ok(policies
+            .into_iter()
+            .find(|policy| Self::get_compiled(policy).matches(&method, path)))
+    }
+
+    /**
+    Forget the compiled patterns of a deleted policy.
+    */
+    pub fn forget_policy(id: &str) {
+        COMPILED_POLICIES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+    }
+
+    /**
+    Get the compiled patterns of the policy, compiling them if the policy is new or changed.
+    */
+    fn get_compiled(policy: &Policy) -> Arc<CompiledPolicy> {
+        let id = policy.get_id();
+        let compiled = COMPILED_POLICIES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&id)
+            .filter(|compiled| compiled.is_current(policy))
+            .cloned();
+        if let Some(compiled) = compiled {
+            return compiled;
+        }
+
+        let compiled = Arc::new(CompiledPolicy::new(policy));
+        COMPILED_POLICIES
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, compiled.clone());
+        compiled
+    }
+}
+
+impl CompiledPolicy {
+    fn new(policy: &Policy) -> Self {
+        let path_regex = policy.get_path_syntax().compile(policy.get_path());
+        Self {
+            path: policy.get_path().to_string(),
+            path_syntax: policy.get_path_syntax(),
+            method: policy.get_method().to_string(),
+            path_regex: valid(policy.get_path(), path_regex),
+            method_regex: valid(policy.get_method(), compile_method(policy.get_method())),
+        }
+    }
+
+    /**
+    Check if the patterns are still the policy's, as it may have been updated.
+    */
+    fn is_current(&self, policy: &Policy) -> bool {
+        self.path == policy.get_path()
+            && self.path_syntax == policy.get_path_syntax()
+            && self.method == policy.get_method()
+    }
+
+    fn matches(&self, method: &str, path: &str) -> bool {
+        let matches = |regex: &Option<Regex>, value| {
+            regex.as_ref().is_some_and(|regex| regex.is_match(value))
+        };
+        matches(&self.path_regex, path) && matches(&self.method_regex, method)
+    }
+}
+
+// Functions
+/**
+Encode the identity's id and bearer as the bearer header's value.
+*/
+pub fn encode_bearer(identity_id: &str, bearer: &str) -> String {
+    STANDARD.encode(format!("{identity_id}:{bearer}"))
+}
+
+fn decode_bearer(bearer: &str) -> Option<(String, String)> {
+    let decoded = STANDARD.decode(bearer.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    Some((id.to_string(), secret.to_string()))
+}
+
+/**
+Compile the method pattern, case-insensitively.
+The `^ALL$` sugar matches all the known HTTP methods, unlike `.*` which also matches the nonstandard ones.
+*/
+pub fn compile_method(pattern: &str) -> Result<Regex, String> {
+    let regex = match pattern.eq_ignore_ascii_case(ALL_METHODS) {
+        true => Regex::new(&format!("^({})$", HTTP_METHODS.join("|"))),
+        false => RegexBuilder::new(pattern).case_insensitive(true).build(),
+    };
+    regex.map_err(|e| e.to_string())
+}
+
+/**
+Keep the compiled pattern if it's valid. An invalid pattern is logged once, when it's compiled.
+*/
+fn valid(pattern: &str, regex: Result<Regex, String>) -> Option<Regex> {
+    regex
+        .inspect_err(|e| warn!(pattern, error = %e, "Invalid policy pattern."))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::PolicyIn;
+
+    #[test]
+    fn compiles_all_to_the_known_methods() {
+        for pattern in [ALL_METHODS, "^all$"] {
+            let regex = compile_method(pattern).unwrap();
+            assert!(HTTP_METHODS.iter().all(|method| regex.is_match(method)));
+            assert!(!regex.is_match("PROPFIND"));
+            assert!(!regex.is_match("ALL"));
+        }
+    }
+
+    #[test]
+    fn matches_the_methods_case_insensitively() {
+        let regex = compile_method("^(GET|post)$").unwrap();
+        assert!(regex.is_match("GET"));
+        assert!(regex.is_match("get"));
+        assert!(regex.is_match("POST"));
+        assert!(!regex.is_match("PUT"));
+    }
+
+    #[test]
+    fn compiles_each_policy_once() {
+        let policy = Policy::from(PolicyIn::new(
+            String::from("/users/{id}"),
+            PathSyntax::Template,
+            String::from("^GET$"),
+            String::from("owner"),
+            String::from("identity"),
+        ));
+        let compiled = AuthorizationService::get_compiled(&policy);
+        assert!(compiled.matches("GET", "/users/42"));
+        assert!(Arc::ptr_eq(
+            &compiled,
+            &AuthorizationService::get_compiled(&policy)
+        ));
+
+        let mut value = serde_json::to_value(&policy).unwrap();
+        value["method"] = serde_json::Value::from("^POST$");
+        let updated: Policy = serde_json::from_value(value).unwrap();
+        let recompiled = AuthorizationService::get_compiled(&updated);
+        assert!(recompiled.matches("POST", "/users/42"));
+        assert!(!recompiled.matches("GET", "/users/42"));
+
+        AuthorizationService::forget_policy(&policy.get_id());
+        assert!(!Arc::ptr_eq(
+            &recompiled,
+            &AuthorizationService::get_compiled(&updated)
+        ));
+    }
+
+    #[test]
+    fn keeps_the_other_patterns() {
+        let regex = compile_method(r"^\w+$").unwrap();
+        assert!(regex.is_match("PROPFIND"));
+        assert!(compile_method("^(GET$").is_err());
+    }
+}
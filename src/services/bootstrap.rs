@@ -0,0 +1,80 @@
+// Libs
+use super::authorization::encode_bearer;
+use crate::{
+    errors::DBResult,
+    utils::{Secret, FILEDB_SETTINGS},
+};
+use std::{
+    fs::{read_to_string, remove_file, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+use tracing::info;
+
+// Data
+/**
+The file of the bootstrap bearer, in the FileDB directory.
+*/
+const BOOTSTRAP_FILE_NAME: &str = "bootstrap.secret";
+
+/**
+The bootstrap file contains the master credentials, so only the owner may read it.
+*/
+#[cfg(unix)]
+const BOOTSTRAP_FILE_MODE: u32 = 0o600;
+
+// Structs
+/**
+Keep the master bearer generated on the first run until it's revealed.
+It's never logged, so revealing it is the only way to get it.
+*/
+pub struct BootstrapService;
+
+// Implementations
+impl BootstrapService {
+    /**
+    Store the generated master bearer, formatted as the bearer header's value.
+    */
+    pub fn store(identity_id: &str, bearer: &Secret<String>) -> DBResult<()> {
+        let value = encode_bearer(identity_id, bearer.expose());
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(BOOTSTRAP_FILE_MODE);
+        }
+
+        let mut file = options.open(bootstrap_path())?;
+        file.write_all(value.as_bytes())?;
+        file.sync_data()?;
+
+        info!("Generated the master bearer. Reveal it once with `poliwarden reveal-bootstrap`.");
+        Ok(())
+    }
+
+    /**
+    Get the stored bearer and delete it, so it can only be revealed once.
+    */
+    pub fn reveal() -> Result<Secret<String>, String> {
+        let path = bootstrap_path();
+        let bearer = match read_to_string(&path) {
+            Ok(bearer) => Secret::new(bearer),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(String::from(
+                    "There's no bootstrap bearer. It was already revealed or the master credentials were provided.",
+                ))
+            }
+            Err(e) => return Err(format!("Couldn't read the bootstrap bearer: {e}")),
+        };
+
+        remove_file(&path).map_err(|e| format!("Couldn't delete the bootstrap bearer: {e}"))?;
+        Ok(bearer)
+    }
+}
+
+// Functions
+fn bootstrap_path() -> PathBuf {
+    FILEDB_SETTINGS.filedb_path.join(BOOTSTRAP_FILE_NAME)
+}
@@ -0,0 +1,298 @@
+// Libs
+use super::{ChangeService, DatabaseService, Repository};
+use crate::{
+    errors::{DBResult, DatabaseError},
+    schemas::{
+        Bundle, BundleIdentity, BundlePolicy, ChangeAction, ExportOptions, Identity, IdentityIn,
+        ImportOptions, ImportReport, Policy, PolicyIn, QueryOptions,
+    },
+    traits::{Database, ModelProperties},
+};
+use std::collections::HashSet;
+use tracing::info;
+
+// Structs
+/**
+Export and import the identities and policies as bundles.
+It works through the `Database` trait, so the bundles can be moved between backends.
+*/
+pub struct BundleService;
+
+// Implementations
+impl BundleService {
+    pub async fn export(db: &dyn Database, options: &ExportOptions) -> DBResult<Bundle> {
+        info!(
+            include_secrets = options.include_secrets,
+            "Exporting bundle..."
+        );
+
+        let identities = Repository::<Identity>::new(db)
+            .get_all(&QueryOptions::new())
+            .await?;
+        let policies = Repository::<Policy>::new(db)
+            .get_all(&QueryOptions::new())
+            .await?;
+
+        let bundle = Bundle::new(
+            identities
+                .iter()
+                .map(|identity| BundleIdentity::new(identity, options.include_secrets))
+                .collect(),
+            policies.iter().map(BundlePolicy::from).collect(),
+        );
+
+        info!(
+            identities = bundle.identities.len(),
+            policies = bundle.policies.len(),
+            "Bundle exported."
+        );
+        Ok(bundle)
+    }
+
+    /**
+    Import a bundle in a single transaction.
+    The records receive new ids and the policies' references are remapped to them.
+    The created records are recorded as changes by the actor.
+    */
+    pub async fn import(
+        db: &dyn Database,
+        bundle: Bundle,
+        options: &ImportOptions,
+        actor: &str,
+    ) -> DBResult<ImportReport> {
+        info!(dry_run = options.dry_run, "Importing bundle...");
+
+        let mut report = ImportReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+        let identities = Repository::<Identity>::new(db);
+        let policies = Repository::<Policy>::new(db);
+
+        let existing_identities = identities.get_all(&QueryOptions::new()).await?;
+        let mut new_identities = Vec::new();
+        let mut bundle_names = HashSet::new();
+        for bundle_identity in bundle.identities {
+            // The names are unique, so a name listed twice would create two identities.
+            if !bundle_names.insert(bundle_identity.name.clone()) {
+                return Err(DatabaseError::Conflict(format!(
+                    "The bundle has more than one identity named {}.",
+                    bundle_identity.name
+                )));
+            }
+
+            let matches: Vec<&Identity> = existing_identities
+                .iter()
+                .filter(|identity| identity.get_name() == bundle_identity.name)
+                .collect();
+
+            let identity_id = match matches.as_slice() {
+                [] => {
+                    let identity = Self::new_identity(bundle_identity.clone())?;
+                    report.created_identities += 1;
+                    let identity_id = identity.get_id();
+                    new_identities.push(identity);
+                    identity_id
+                }
+                [identity] => {
+                    report.matched_identities += 1;
+                    identity.get_id()
+                }
+                _ => {
+                    return Err(DatabaseError::Conflict(format!(
+                        "More than one identity is named {}.",
+                        bundle_identity.name
+                    )))
+                }
+            };
+            report.identity_ids.insert(bundle_identity.id, identity_id);
+        }
+
+        let existing_policies = policies.get_all(&QueryOptions::new()).await?;
+        let mut new_policies: Vec<Policy> = Vec::new();
+        for bundle_policy in bundle.policies {
+            let owner_id = Self::remap_identity_id(&report, &bundle_policy.owner_id, db).await?;
+            let identity_id =
+                Self::remap_identity_id(&report, &bundle_policy.identity_id, db).await?;
+            let policy: Policy = PolicyIn::new(
+                bundle_policy.path,
+                bundle_policy.path_syntax,
+                bundle_policy.method,
+                owner_id,
+                identity_id,
+            )
+            .into();
+
+            let is_duplicated = existing_policies
+                .iter()
+                .chain(new_policies.iter())
+                .any(|existing_policy| Self::is_same_policy(existing_policy, &policy));
+            match is_duplicated {
+                true => report.skipped_policies += 1,
+                false => new_policies.push(policy),
+            }
+        }
+        report.created_policies = new_policies.len();
+
+        if !options.dry_run {
+            DatabaseService::transaction(db, async {
+                for identity in &new_identities {
+                    identities.insert(identity).await?;
+                    ChangeService::record(db, actor, ChangeAction::Create, None, Some(identity))
+                        .await?;
+                }
+                for policy in &new_policies {
+                    policies.insert(policy).await?;
+                    ChangeService::record(db, actor, ChangeAction::Create, None, Some(policy))
+                        .await?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
+
+        info!(report = ?report, "Bundle imported.");
+        Ok(report)
+    }
+
+    fn new_identity(bundle_identity: BundleIdentity) -> DBResult<Identity> {
+        let identity = Identity::try_from(IdentityIn::new(
+            bundle_identity.name,
+            bundle_identity.host,
+            None,
+            None,
+        ))?;
+        match bundle_identity.secrets {
+            Some(secrets) => Ok(identity.with_secrets(secrets)),
+            None => Ok(identity),
+        }
+    }
+
+    /**
+    Get the database id of a bundle's identity id.
+    Ids outside the bundle are kept if the identity exists in the database.
+    */
+    async fn remap_identity_id(
+        report: &ImportReport,
+        identity_id: &str,
+        db: &dyn Database,
+    ) -> DBResult<String> {
+        if let Some(identity_id) = report.identity_ids.get(identity_id) {
+            return Ok(identity_id.clone());
+        }
+
+        match Repository::<Identity>::new(db).get(identity_id).await? {
+            Some(identity) => Ok(identity.get_id()),
+            None => Err(DatabaseError::InvalidField(format!(
+                "The policy references the unknown identity {identity_id}."
+            ))),
+        }
+    }
+
+    fn is_same_policy(a: &Policy, b: &Policy) -> bool {
+        a.get_path() == b.get_path()
+            && a.get_path_syntax() == b.get_path_syntax()
+            && a.get_method() == b.get_method()
+            && a.get_owner_id() == b.get_owner_id()
+            && a.get_identity_id() == b.get_identity_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::databases::FileDatabase;
+    use crate::traits::SerdeModel;
+
+    fn bundle_identity(id: &str, name: &str) -> BundleIdentity {
+        BundleIdentity {
+            id: id.to_string(),
+            name: name.to_string(),
+            host: String::from("billing.local"),
+            secrets: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_the_names_listed_twice() {
+        let (_directory, db) = FileDatabase::temporary().await;
+        let bundle = Bundle::new(
+            vec![
+                bundle_identity("first", "billing"),
+                bundle_identity("second", "billing"),
+            ],
+            Vec::new(),
+        );
+
+        let result = BundleService::import(&db, bundle, &ImportOptions::default(), "test").await;
+        assert!(matches!(result, Err(DatabaseError::Conflict(_))));
+
+        let identities = Repository::<Identity>::new(&db)
+            .get_all(&QueryOptions::new())
+            .await
+            .unwrap();
+        assert!(identities.is_empty());
+    }
+
+    #[tokio::test]
+    async fn records_the_created_records_by_the_actor() {
+        let (_directory, db) = FileDatabase::temporary().await;
+        let policy = BundlePolicy {
+            id: String::from("policy"),
+            path: String::from("/invoices"),
+            path_syntax: Default::default(),
+            method: String::from("^GET$"),
+            owner_id: String::from("first"),
+            identity_id: String::from("first"),
+        };
+        let bundle = Bundle::new(vec![bundle_identity("first", "billing")], vec![policy]);
+
+        let report = BundleService::import(&db, bundle, &ImportOptions::default(), "importer")
+            .await
+            .unwrap();
+        let identity_id = &report.identity_ids["first"];
+        let policies = Repository::<Policy>::new(&db)
+            .find_by("identity_id", identity_id)
+            .await
+            .unwrap();
+
+        for id in [identity_id.clone(), policies[0].get_id()] {
+            assert_eq!(
+                ChangeService::get_history(&db, &id).await,
+                [(ChangeAction::Create, String::from("importer"))]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn matches_the_existing_names() {
+        let (_directory, db) = FileDatabase::temporary().await;
+        let bundle = Bundle::new(vec![bundle_identity("first", "billing")], Vec::new());
+        let options = ImportOptions::default();
+
+        let first = BundleService::import(&db, bundle.clone(), &options, "test").await;
+        let second = BundleService::import(&db, bundle, &options, "test").await;
+        assert_eq!(first.unwrap().created_identities, 1);
+        assert_eq!(second.unwrap().matched_identities, 1);
+    }
+
+    #[tokio::test]
+    async fn leaves_no_partial_records_when_it_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let db = FileDatabase::new(directory.path());
+        db.connect().await.unwrap();
+        // Without the change table, the identity is inserted but its change can't be recorded.
+        db.create_table(&Identity::table_name()).await.unwrap();
+        db.create_table(&Policy::table_name()).await.unwrap();
+        let bundle = Bundle::new(vec![bundle_identity("first", "billing")], Vec::new());
+
+        let result = BundleService::import(&db, bundle, &ImportOptions::default(), "test").await;
+        assert!(result.is_err());
+
+        let identities = Repository::<Identity>::new(&db)
+            .get_all(&QueryOptions::new())
+            .await
+            .unwrap();
+        assert!(identities.is_empty());
+    }
+}
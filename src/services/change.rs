@@ -0,0 +1,94 @@
+// Libs
+use super::{DatabaseService, Repository};
+use crate::{
+    errors::{AppError, AppResult, DBResult},
+    schemas::{ChangeAction, ChangeQuery, ChangeRecord, Identity, Policy, QueryOptions, SortOrder},
+    traits::{Database, SerdeModel, Tracked},
+};
+use tracing::debug;
+
+// Structs
+/**
+Record and query the management changes of the identities and policies.
+*/
+pub struct ChangeService;
+
+// Implementations
+impl ChangeService {
+    /**
+    Record a change. It should run in the transaction of the change, so both are kept or neither.
+    */
+    pub async fn record<T: Tracked>(
+        db: &dyn Database,
+        actor: &str,
+        action: ChangeAction,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> DBResult<()> {
+        let record = ChangeRecord::new(actor, action, before, after)?;
+        Repository::<ChangeRecord>::new(db).insert(&record).await?;
+
+        debug!(
+            entity = record.get_entity(),
+            entity_id = record.get_entity_id(),
+            action = ?record.get_action(),
+            actor,
+            "Change recorded."
+        );
+        Ok(())
+    }
+
+    /**
+    Get the changes, oldest first, filtered by the entity and its id.
+    */
+    pub async fn get_all(query: &ChangeQuery) -> AppResult<Vec<ChangeRecord>> {
+        // The ids are sortable by their creation time, so the cursor follows the same order.
+        let mut options = QueryOptions::new().sort("id", SortOrder::Asc);
+        if let Some(entity) = &query.entity {
+            if ![Identity::table_name(), Policy::table_name()].contains(entity) {
+                return Err(AppError::Validation(format!(
+                    "The entity must be `identity` or `policy`, got `{entity}`."
+                )));
+            }
+            options = options.filter("entity", entity);
+        }
+        if let Some(id) = &query.id {
+            options = options.filter("entity_id", id);
+        }
+        if let Some(limit) = query.limit {
+            options = options.limit(limit);
+        }
+        if let Some(cursor) = query.cursor {
+            options = options.cursor(cursor);
+        }
+
+        let db = DatabaseService::get_database()?;
+        let db = db.read().await;
+        Ok(Repository::<ChangeRecord>::new(db.as_ref())
+            .get_all(&options)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+impl ChangeService {
+    /**
+    Get the actions and actors of an entity's changes, in the order they were recorded.
+    */
+    pub async fn get_history(db: &dyn Database, entity_id: &str) -> Vec<(ChangeAction, String)> {
+        let records = Repository::<ChangeRecord>::new(db)
+            .find_by("entity_id", entity_id)
+            .await
+            .unwrap();
+        records
+            .iter()
+            .map(|record| {
+                let fields = serde_json::to_value(record).unwrap();
+                (
+                    record.get_action(),
+                    fields["actor"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+}
@@ -1,13 +1,29 @@
 // libs
-use super::Migrations;
-use crate::{databases::FileDatabase, errors::DBResult, traits::Database};
-use std::sync::Arc;
+use super::{Migrations, PolicyFileService, Repository};
+use crate::{
+    databases::{FileDatabase, InstrumentedDatabase},
+    errors::{DBResult, DatabaseError},
+    schemas::ChangeRecord,
+    traits::Database,
+    utils::FILEDB_SETTINGS,
+};
+use clap::ValueEnum;
+use std::{fs::canonicalize, future::Future, path::PathBuf, sync::Arc};
 use tokio::sync::{OnceCell, RwLock};
-use tracing::info;
+use tracing::{error, info};
 
 // Data
 pub static DATABASE: OnceCell<Arc<RwLock<Box<dyn Database>>>> = OnceCell::const_new();
 
+// Enums
+/**
+The database backends built in the application.
+*/
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum DatabaseBackend {
+    Filedb,
+}
+
 // Structs
 pub struct DatabaseService;
 
@@ -21,23 +37,109 @@ impl DatabaseService {
             })
             .await;
 
+        db.read().await.connect().await?;
         if db.read().await.is_first_run().await? {
             DatabaseService::run_migrations().await?;
         }
+        // The change table was added after the first release, so it's created on every start.
+        Repository::<ChangeRecord>::new(db.read().await.as_ref())
+            .create_table()
+            .await?;
+        PolicyFileService::reconcile_all(db.write().await.as_ref()).await?;
 
         info!("Database initialized.");
         Ok(())
     }
 
+    /**
+    Get the initialized database.
+    */
+    pub fn get_database() -> DBResult<Arc<RwLock<Box<dyn Database>>>> {
+        DATABASE.get().cloned().ok_or(DatabaseError::Uninitialized)
+    }
+
+    /**
+    Run the operations in a transaction, committing them if they succeed or rolling them back otherwise.
+    The operations must use the same database.
+    */
+    pub async fn transaction<T>(
+        db: &dyn Database,
+        operations: impl Future<Output = DBResult<T>>,
+    ) -> DBResult<T> {
+        db.begin().await?;
+        match operations.await {
+            Ok(result) => {
+                db.commit().await?;
+                Ok(result)
+            }
+            Err(e) => {
+                if let Err(rollback_error) = db.rollback().await {
+                    error!(error = %rollback_error, "Couldn't rollback the transaction.");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /**
+    Create a database instance of the backend.
+    The location is backend specific. For the FileDB, it's the directory of the tables.
+    */
+    pub fn create_database(
+        backend: DatabaseBackend,
+        location: Option<PathBuf>,
+    ) -> Box<dyn Database> {
+        let location = Self::resolve_location(backend, location);
+        match backend {
+            DatabaseBackend::Filedb => Box::new(InstrumentedDatabase::new(Box::new(
+                FileDatabase::new(location).with_encryption(
+                    FILEDB_SETTINGS.filedb_key,
+                    FILEDB_SETTINGS.filedb_previous_key,
+                ),
+            ))),
+        }
+    }
+
+    /**
+    Get the effective location of a backend's store, defaulting to the configured one.
+    It's canonicalized if it exists, so different paths to the same store are equal.
+    */
+    pub fn resolve_location(backend: DatabaseBackend, location: Option<PathBuf>) -> PathBuf {
+        let location = match backend {
+            DatabaseBackend::Filedb => {
+                location.unwrap_or_else(|| FILEDB_SETTINGS.filedb_path.clone())
+            }
+        };
+        canonicalize(&location).unwrap_or(location)
+    }
+
     fn get_enabled_database() -> Box<dyn Database> {
         info!("FileDB selected.");
-        Box::new(FileDatabase::default())
+        Box::new(InstrumentedDatabase::new(Box::new(FileDatabase::default())))
     }
 
     /**
     A method to be called on the first run of the application.
     */
     async fn run_migrations() -> DBResult<()> {
-        Migrations::new().run().await
+        Migrations::new()?.run().await
+    }
+}
+
+#[cfg(test)]
+impl DatabaseService {
+    /**
+    Initialize the database of the services with a temporary FileDB, shared by the tests.
+    The database is never dropped, so its directory is kept until it's cleaned by the system.
+    */
+    pub async fn init_temporary() {
+        DATABASE
+            .get_or_init(|| async {
+                let (directory, db) = FileDatabase::temporary().await;
+                let _ = directory.into_path();
+                let db: Box<dyn Database> = Box::new(db);
+                Arc::new(RwLock::new(db))
+            })
+            .await;
     }
 }
@@ -0,0 +1,101 @@
+// Libs
+use super::{DatabaseService, PolicyFileService, Repository};
+use crate::{
+    schemas::{HealthCheck, HealthReport, Policy, QueryOptions},
+    traits::Database,
+};
+use std::{future::Future, time::Duration};
+use tokio::time::timeout;
+
+// Data
+/**
+The time a readiness check can take before it fails, so a hanging database doesn't hang the probe.
+*/
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Structs
+/**
+The liveness and readiness checks of the application.
+*/
+pub struct HealthService;
+
+// Implementations
+impl HealthService {
+    /**
+    The process is alive while it answers, so there's nothing to check.
+    */
+    pub fn liveness() -> HealthReport {
+        HealthReport::new(Vec::new())
+    }
+
+    /**
+    Check if the database is reachable, the migrations were applied and the policies were loaded.
+    */
+    pub async fn readiness() -> HealthReport {
+        let db = match DatabaseService::get_database() {
+            Ok(db) => db,
+            Err(e) => return unchecked(e.to_string()),
+        };
+        // The lock is held for writing while the migrations and reconciliations run.
+        let Ok(db) = timeout(CHECK_TIMEOUT, db.read()).await else {
+            return unchecked(String::from("The database is locked by another operation."));
+        };
+        let db = db.as_ref();
+
+        HealthReport::new(vec![
+            HealthCheck::new(
+                "database",
+                check(async { db.ping().await.map(|_| None) }).await,
+            ),
+            HealthCheck::new("migrations", check(check_migrations(db)).await),
+            HealthCheck::new("policies", check(check_policies(db)).await),
+        ])
+    }
+}
+
+// Functions
+/**
+Fail the readiness when the database can't be checked.
+*/
+fn unchecked(reason: String) -> HealthReport {
+    HealthReport::new(vec![
+        HealthCheck::new("database", Err(reason)),
+        HealthCheck::new("migrations", Err(String::from("Not checked."))),
+        HealthCheck::new("policies", Err(String::from("Not checked."))),
+    ])
+}
+
+/**
+Run a check with the timeout, turning its error into the detail.
+*/
+async fn check<E: ToString>(
+    operation: impl Future<Output = Result<Option<String>, E>>,
+) -> Result<Option<String>, String> {
+    match timeout(CHECK_TIMEOUT, operation).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!(
+            "Timed out after {} seconds.",
+            CHECK_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+async fn check_migrations(db: &dyn Database) -> Result<Option<String>, String> {
+    match db.is_first_run().await {
+        Ok(false) => Ok(None),
+        Ok(true) => Err(String::from("The migrations weren't applied.")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn check_policies(db: &dyn Database) -> Result<Option<String>, String> {
+    if !PolicyFileService::is_reconciled() {
+        return Err(String::from("The policy documents weren't reconciled."));
+    }
+
+    let count = Repository::<Policy>::new(db)
+        .count(&QueryOptions::new())
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(format!("{count} policies loaded.")))
+}
@@ -1 +1,272 @@
-//
+// Libs
+use super::{ChangeService, DatabaseService, Repository};
+use crate::{
+    errors::{AppError, AppResult, DatabaseError},
+    schemas::{ChangeAction, Identity, IdentityIn, IdentityOut, Policy, QueryOptions},
+    traits::ModelProperties,
+};
+
+// Data
+/**
+The identities created on the first run. The master manages the application and the PoliWarden owns its policies.
+*/
+const SEEDED_IDENTITIES: [&str; 2] = ["master", "poliwarden"];
+
+// Structs
+/**
+The management operations of the identities.
+*/
+pub struct IdentityService;
+
+// Implementations
+impl IdentityService {
+    pub async fn get(id: &str) -> AppResult<IdentityOut> {
+        let db = DatabaseService::get_database()?;
+        let db = db.read().await;
+        match Repository::<Identity>::new(db.as_ref()).get(id).await? {
+            Some(identity) => Ok(identity.into()),
+            None => Err(not_found(id)),
+        }
+    }
+
+    pub async fn get_all(options: &QueryOptions) -> AppResult<Vec<IdentityOut>> {
+        options.validate()?;
+
+        let db = DatabaseService::get_database()?;
+        let db = db.read().await;
+        let identities = Repository::<Identity>::new(db.as_ref())
+            .get_all(options)
+            .await?;
+        Ok(identities.into_iter().map(IdentityOut::from).collect())
+    }
+
+    /**
+    Create an identity, recording the change by the actor.
+    */
+    pub async fn create(identity: IdentityIn, actor: &str) -> AppResult<IdentityOut> {
+        let identity = Identity::try_from(identity)?;
+
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let identities = Repository::<Identity>::new(db);
+
+        check_unique_name(&identities, &identity).await?;
+        DatabaseService::transaction(db, async {
+            identities.insert(&identity).await?;
+            ChangeService::record(db, actor, ChangeAction::Create, None, Some(&identity)).await
+        })
+        .await?;
+        Ok(identity.into())
+    }
+
+    /**
+    Update an identity, recording the change by the actor.
+    The revision is the one the client has seen, so concurrent updates aren't lost.
+    */
+    pub async fn update(
+        id: &str,
+        identity: IdentityIn,
+        revision: u64,
+        actor: &str,
+    ) -> AppResult<IdentityOut> {
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let identities = Repository::<Identity>::new(db);
+
+        let Some(stored_identity) = identities.get(id).await? else {
+            return Err(not_found(id));
+        };
+        let mut identity_update = stored_identity.clone();
+        identity_update.update_from(identity)?;
+        check_unique_name(&identities, &identity_update).await?;
+
+        let updated = DatabaseService::transaction(db, async {
+            let Some(updated) = identities.update(&identity_update, Some(revision)).await? else {
+                return Ok(None);
+            };
+            ChangeService::record(
+                db,
+                actor,
+                ChangeAction::Update,
+                Some(&stored_identity),
+                Some(&updated),
+            )
+            .await?;
+            Ok::<_, DatabaseError>(Some(updated))
+        })
+        .await?;
+
+        match updated {
+            Some(identity) => Ok(identity.into()),
+            None => Err(not_found(id)),
+        }
+    }
+
+    /**
+    Replace an identity's bearer, recording the change by the actor.
+    The bearer is only known by the caller, as the identity only stores its hash.
+    */
+    pub async fn rotate_bearer(id: &str, bearer: &str, actor: &str) -> AppResult<IdentityOut> {
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let identities = Repository::<Identity>::new(db);
+
+        let Some(stored_identity) = identities.get(id).await? else {
+            return Err(not_found(id));
+        };
+        let mut identity_update = stored_identity.clone();
+        identity_update.set_bearer(bearer)?;
+
+        let updated = DatabaseService::transaction(db, async {
+            let revision = Some(stored_identity.get_revision());
+            let Some(updated) = identities.update(&identity_update, revision).await? else {
+                return Ok(None);
+            };
+            ChangeService::record(
+                db,
+                actor,
+                ChangeAction::Update,
+                Some(&stored_identity),
+                Some(&updated),
+            )
+            .await?;
+            Ok::<_, DatabaseError>(Some(updated))
+        })
+        .await?;
+
+        match updated {
+            Some(identity) => Ok(identity.into()),
+            None => Err(not_found(id)),
+        }
+    }
+
+    /**
+    Delete an identity, recording the change by the actor.
+    The seeded identities and the identities with policies, as their holder or owner, can't be deleted.
+    */
+    pub async fn delete(id: &str, actor: &str) -> AppResult<()> {
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let identities = Repository::<Identity>::new(db);
+
+        let Some(stored_identity) = identities.get(id).await? else {
+            return Err(not_found(id));
+        };
+        if SEEDED_IDENTITIES.contains(&stored_identity.get_name()) {
+            return Err(AppError::Conflict(format!(
+                "The {} identity can't be deleted.",
+                stored_identity.get_name()
+            )));
+        }
+        let policies = Repository::<Policy>::new(db);
+        let held = policies.find_by("identity_id", id).await?;
+        let owned = policies.find_by("owner_id", id).await?;
+        if !held.is_empty() || !owned.is_empty() {
+            return Err(AppError::Conflict(format!(
+                "The identity {id} still holds {} and owns {} policies. Remove them first.",
+                held.len(),
+                owned.len()
+            )));
+        }
+
+        DatabaseService::transaction(db, async {
+            identities.delete(id).await?;
+            ChangeService::record(
+                db,
+                actor,
+                ChangeAction::Delete,
+                Some(&stored_identity),
+                None,
+            )
+            .await
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+// Functions
+/**
+Check that no other identity has the identity's name, as the policy documents reference the identities by their names.
+*/
+async fn check_unique_name(
+    identities: &Repository<'_, Identity>,
+    identity: &Identity,
+) -> AppResult<()> {
+    let same_name = identities.find_by("name", identity.get_name()).await?;
+    match same_name
+        .iter()
+        .any(|other| other.get_id() != identity.get_id())
+    {
+        true => Err(AppError::Conflict(format!(
+            "An identity is already named {}.",
+            identity.get_name()
+        ))),
+        false => Ok(()),
+    }
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("The identity {id} doesn't exist."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ulid::Ulid;
+
+    #[tokio::test]
+    async fn records_the_changes_by_the_actor() {
+        DatabaseService::init_temporary().await;
+        let name = format!("identity-{}", Ulid::new());
+        let identity = IdentityIn::new(name.clone(), String::from("a.local"), None, None);
+        let id = IdentityService::create(identity, "creator")
+            .await
+            .unwrap()
+            .get_id();
+
+        let identity = IdentityIn::new(name, String::from("b.local"), None, None);
+        IdentityService::update(&id, identity, 1, "updater")
+            .await
+            .unwrap();
+        IdentityService::rotate_bearer(&id, "bearer", "rotator")
+            .await
+            .unwrap();
+        IdentityService::delete(&id, "deleter").await.unwrap();
+
+        let db = DatabaseService::get_database().unwrap();
+        let history = ChangeService::get_history(db.read().await.as_ref(), &id).await;
+        assert_eq!(
+            history,
+            [
+                (ChangeAction::Create, String::from("creator")),
+                (ChangeAction::Update, String::from("updater")),
+                (ChangeAction::Update, String::from("rotator")),
+                (ChangeAction::Delete, String::from("deleter")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn records_no_change_when_rejected() {
+        DatabaseService::init_temporary().await;
+        let name = format!("identity-{}", Ulid::new());
+        let identity = IdentityIn::new(name.clone(), String::from("a.local"), None, None);
+        let id = IdentityService::create(identity, "creator")
+            .await
+            .unwrap()
+            .get_id();
+
+        let identity = IdentityIn::new(name, String::from("b.local"), None, None);
+        let result = IdentityService::update(&id, identity, 7, "updater").await;
+        assert!(matches!(result, Err(AppError::PreconditionFailed(_))));
+
+        let db = DatabaseService::get_database().unwrap();
+        let history = ChangeService::get_history(db.read().await.as_ref(), &id).await;
+        assert_eq!(history, [(ChangeAction::Create, String::from("creator"))]);
+    }
+}
@@ -0,0 +1,327 @@
+// Libs
+use super::{
+    authorization::{compile_method, ALL_METHODS},
+    Repository,
+};
+use crate::{
+    errors::DBResult,
+    schemas::{Identity, LintRule, LintWarning, PathSyntax, Policy, QueryOptions, HTTP_METHODS},
+    traits::{Database, ModelProperties},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+
+// Data
+/**
+A path no policy should need to match. A pattern matching it matches any path.
+*/
+const PROBE_PATH: &str = "/poliwarden-lint-probe/0/.probe";
+
+/**
+A method no policy should need to match. A pattern matching it matches any method.
+*/
+const PROBE_METHOD: &str = "POLIWARDENLINTPROBE";
+
+/**
+The escaped characters of a pattern, such as `\d`, which aren't part of the method names.
+*/
+static ESCAPES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\\.").expect("The escape pattern is valid."));
+
+// Structs
+/**
+Find the mistakes in the policies, such as unanchored or overly broad patterns.
+The warnings never prevent a policy from being saved.
+*/
+pub struct LintService;
+
+// Implementations
+impl LintService {
+    /**
+    Lint all the policies.
+    */
+    pub async fn lint_all(db: &dyn Database) -> DBResult<Vec<LintWarning>> {
+        let policies = Repository::<Policy>::new(db)
+            .get_all(&QueryOptions::new())
+            .await?;
+        let identity_ids = Self::get_identity_ids(db).await?;
+
+        Ok(policies
+            .iter()
+            .flat_map(|policy| Self::lint(policy, &policies, &identity_ids))
+            .collect())
+    }
+
+    /**
+    Lint a policy. The duplicated and shadowing policies are found among the identity's other policies.
+    */
+    pub fn lint(
+        policy: &Policy,
+        policies: &[Policy],
+        identity_ids: &HashSet<String>,
+    ) -> Vec<LintWarning> {
+        let policy_id = policy.get_id();
+        let mut warnings = Vec::new();
+        let mut warn = |rule, message| warnings.push(LintWarning::new(&policy_id, rule, message));
+
+        let path = policy.get_path_syntax().compile(policy.get_path());
+        let method = compile_method(policy.get_method());
+        // The globs and templates are always anchored.
+        let is_regex = policy.get_path_syntax() == PathSyntax::Regex;
+        let fields = [
+            ("path", policy.get_path(), &path, PROBE_PATH, is_regex),
+            ("method", policy.get_method(), &method, PROBE_METHOD, true),
+        ];
+        for (field, pattern, regex, probe, can_be_unanchored) in fields {
+            match regex {
+                Err(e) => warn(
+                    LintRule::InvalidPattern,
+                    format!("The {field} `{pattern}` is invalid, so the policy never matches: {e}"),
+                ),
+                Ok(regex) if regex.is_match(probe) => warn(
+                    LintRule::Broad,
+                    format!("The {field} `{pattern}` matches any {field}."),
+                ),
+                Ok(_) if can_be_unanchored && !is_anchored(pattern) => warn(
+                    LintRule::Unanchored,
+                    format!("The {field} `{pattern}` isn't anchored by `^` and `$`, so it matches any {field} containing it."),
+                ),
+                Ok(_) => {}
+            }
+        }
+
+        if let Ok(method) = &method {
+            if !method.is_match(PROBE_METHOD)
+                && !HTTP_METHODS.iter().any(|known| method.is_match(known))
+            {
+                warn(
+                    LintRule::UnknownMethod,
+                    format!(
+                        "The method `{}` matches no HTTP method.",
+                        policy.get_method()
+                    ),
+                );
+            }
+        }
+        for token in unknown_methods(policy.get_method()) {
+            warn(
+                LintRule::UnknownMethod,
+                format!("`{token}` isn't an HTTP method."),
+            );
+        }
+
+        for (role, id) in [
+            ("identity", policy.get_identity_id()),
+            ("owner", policy.get_owner_id()),
+        ] {
+            if !identity_ids.contains(id) {
+                warn(
+                    LintRule::MissingIdentity,
+                    format!("The {role} {id} doesn't exist."),
+                );
+            }
+        }
+
+        let others = policies.iter().filter(|other| {
+            other.get_id() != policy_id && other.get_identity_id() == policy.get_identity_id()
+        });
+        for other in others {
+            if other.get_path() == policy.get_path()
+                && other.get_path_syntax() == policy.get_path_syntax()
+                && other.get_method() == policy.get_method()
+            {
+                warn(
+                    LintRule::Duplicate,
+                    format!(
+                        "The policy {} has the same path and method.",
+                        other.get_id()
+                    ),
+                );
+            } else if path.is_ok() && is_catch_all_for(other, method.as_ref().ok()) {
+                warn(
+                    LintRule::ShadowedByCatchAll,
+                    format!(
+                        "The policy {} already allows any path with the policy's methods.",
+                        other.get_id()
+                    ),
+                );
+            }
+        }
+
+        warnings
+    }
+
+    /**
+    Get the ids of the identities, to find the policies referencing missing ones.
+    */
+    pub async fn get_identity_ids(db: &dyn Database) -> DBResult<HashSet<String>> {
+        let identities = Repository::<Identity>::new(db)
+            .get_all(&QueryOptions::new())
+            .await?;
+        Ok(identities
+            .iter()
+            .map(|identity| identity.get_id())
+            .collect())
+    }
+}
+
+// Functions
+fn is_anchored(pattern: &str) -> bool {
+    let starts = pattern.starts_with('^') || pattern.starts_with("\\A");
+    let ends = (pattern.ends_with('$') && !pattern.ends_with("\\$")) || pattern.ends_with("\\z");
+    starts && ends
+}
+
+/**
+Get the words of the method pattern that aren't HTTP methods, ignoring its escapes and single letters.
+*/
+fn unknown_methods(pattern: &str) -> Vec<String> {
+    if pattern.eq_ignore_ascii_case(ALL_METHODS) {
+        return Vec::new();
+    }
+
+    let unescaped = ESCAPES.replace_all(pattern, " ");
+
+    let mut tokens: Vec<String> = unescaped
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|token| token.len() > 1)
+        .map(str::to_uppercase)
+        .filter(|token| !HTTP_METHODS.contains(&token.as_str()))
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+/**
+Check if the other policy matches any path and every HTTP method the policy's method matches.
+*/
+fn is_catch_all_for(other: &Policy, method: Option<&Regex>) -> bool {
+    let (Ok(other_path), Ok(other_method)) = (
+        other.get_path_syntax().compile(other.get_path()),
+        compile_method(other.get_method()),
+    ) else {
+        return false;
+    };
+    let Some(method) = method else {
+        return false;
+    };
+
+    let methods: Vec<&&str> = HTTP_METHODS
+        .iter()
+        .filter(|known| method.is_match(known))
+        .collect();
+    other_path.is_match(PROBE_PATH)
+        && !methods.is_empty()
+        && methods.iter().all(|known| other_method.is_match(known))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::PolicyIn;
+
+    const IDENTITY_ID: &str = "identity";
+    const OWNER_ID: &str = "owner";
+
+    fn policy(path: &str, path_syntax: PathSyntax, method: &str) -> Policy {
+        Policy::from(PolicyIn::new(
+            path.to_string(),
+            path_syntax,
+            method.to_string(),
+            OWNER_ID.to_string(),
+            IDENTITY_ID.to_string(),
+        ))
+    }
+
+    fn rules(policy: &Policy, policies: &[Policy]) -> Vec<LintRule> {
+        let identity_ids = HashSet::from([IDENTITY_ID.to_string(), OWNER_ID.to_string()]);
+        LintService::lint(policy, policies, &identity_ids)
+            .into_iter()
+            .map(|warning| warning.rule)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_a_narrow_policy() {
+        let policy = policy(r"^\/users\/?$", PathSyntax::Regex, "^(GET|HEAD)$");
+        assert!(rules(&policy, &[]).is_empty());
+        let all_methods = self::policy("/users/{id}", PathSyntax::Template, ALL_METHODS);
+        assert!(rules(&all_methods, &[]).is_empty());
+    }
+
+    #[test]
+    fn warns_about_invalid_patterns() {
+        let policy = policy(r"^\/users\/($", PathSyntax::Regex, "^(GET$");
+        assert_eq!(
+            rules(&policy, &[]),
+            [LintRule::InvalidPattern, LintRule::InvalidPattern]
+        );
+    }
+
+    #[test]
+    fn warns_about_unanchored_regexes_only() {
+        let policy = policy(r"\/users", PathSyntax::Regex, "GET");
+        assert_eq!(
+            rules(&policy, &[]),
+            [LintRule::Unanchored, LintRule::Unanchored]
+        );
+
+        let glob = self::policy("/users/*", PathSyntax::Glob, "^GET$");
+        assert!(rules(&glob, &[]).is_empty());
+    }
+
+    #[test]
+    fn warns_about_broad_patterns() {
+        let policy = policy("^.*$", PathSyntax::Regex, "^.*$");
+        assert_eq!(rules(&policy, &[]), [LintRule::Broad, LintRule::Broad]);
+        let glob = self::policy("/**", PathSyntax::Glob, "^GET$");
+        assert_eq!(rules(&glob, &[]), [LintRule::Broad]);
+    }
+
+    #[test]
+    fn warns_about_unknown_methods() {
+        let policy = policy(r"^\/users$", PathSyntax::Regex, r"^(GET|FETCH)\w?$");
+        assert_eq!(rules(&policy, &[]), [LintRule::UnknownMethod]);
+        let none = self::policy(r"^\/users$", PathSyntax::Regex, "^FETCH$");
+        assert_eq!(
+            rules(&none, &[]),
+            [LintRule::UnknownMethod, LintRule::UnknownMethod]
+        );
+    }
+
+    #[test]
+    fn warns_about_missing_identities() {
+        let policy = policy(r"^\/users$", PathSyntax::Regex, "^GET$");
+        let identity_ids = HashSet::from([IDENTITY_ID.to_string()]);
+        let warnings = LintService::lint(&policy, &[], &identity_ids);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].rule, LintRule::MissingIdentity);
+        assert!(warnings[0].message.contains(OWNER_ID));
+
+        assert_eq!(LintService::lint(&policy, &[], &HashSet::new()).len(), 2);
+    }
+
+    #[test]
+    fn warns_about_duplicates() {
+        let policy = policy(r"^\/users$", PathSyntax::Regex, "^GET$");
+        let duplicate = self::policy(r"^\/users$", PathSyntax::Regex, "^GET$");
+        let other_syntax = self::policy(r"^\/users$", PathSyntax::Glob, "^GET$");
+        let policies = [policy.clone(), duplicate, other_syntax];
+        assert_eq!(rules(&policy, &policies), [LintRule::Duplicate]);
+    }
+
+    #[test]
+    fn warns_about_the_catch_all_policies_only() {
+        let policy = policy(r"^\/users$", PathSyntax::Regex, "^GET$");
+        let catch_all = self::policy("/**", PathSyntax::Glob, "^(GET|POST)$");
+        let other_methods = self::policy("/**", PathSyntax::Glob, "^POST$");
+        let narrower = self::policy(r"^\/users\/?$", PathSyntax::Regex, "^GET$");
+
+        let policies = [policy.clone(), catch_all.clone()];
+        assert_eq!(rules(&policy, &policies), [LintRule::ShadowedByCatchAll]);
+        let policies = [policy.clone(), other_methods, narrower];
+        assert!(rules(&policy, &policies).is_empty());
+    }
+}
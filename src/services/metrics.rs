@@ -0,0 +1,148 @@
+// Libs
+use crate::schemas::{Decision, HTTP_METHODS};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::time::Duration;
+use tracing::error;
+
+// Data
+/**
+The application's metrics. If they can't be registered, the metrics are disabled and the error is logged.
+*/
+static METRICS: Lazy<Option<Metrics>> = Lazy::new(|| match Metrics::new() {
+    Ok(metrics) => Some(metrics),
+    Err(e) => {
+        error!(error = %e, "Couldn't register the metrics.");
+        None
+    }
+});
+
+// Structs
+struct Metrics {
+    registry: Registry,
+    decisions: IntCounterVec,
+    upstream_duration: HistogramVec,
+    database_duration: HistogramVec,
+}
+
+/**
+Record and export the Prometheus metrics.
+*/
+pub struct MetricsService;
+
+// Implementations
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some(String::from("poliwarden")), None)?;
+
+        let decisions = IntCounterVec::new(
+            Opts::new("decisions_total", "The authorization decisions."),
+            &["decision", "identity_id", "policy_id"],
+        )?;
+        let upstream_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "upstream_duration_seconds",
+                "The latency of the proxied upstream requests.",
+            ),
+            &["host", "method", "status"],
+        )?;
+        let database_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "database_duration_seconds",
+                "The latency of the database operations.",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+            ]),
+            &["backend", "table", "operation"],
+        )?;
+
+        registry.register(Box::new(decisions.clone()))?;
+        registry.register(Box::new(upstream_duration.clone()))?;
+        registry.register(Box::new(database_duration.clone()))?;
+
+        Ok(Self {
+            registry,
+            decisions,
+            upstream_duration,
+            database_duration,
+        })
+    }
+}
+
+impl MetricsService {
+    pub fn record_decision(decision: &Decision) {
+        if let Some(metrics) = METRICS.as_ref() {
+            metrics
+                .decisions
+                .with_label_values(&[
+                    decision.name(),
+                    decision.get_identity_id().unwrap_or_default(),
+                    decision.get_policy_id().unwrap_or_default(),
+                ])
+                .inc();
+        }
+    }
+
+    /**
+    Record the latency of a request forwarded to an identity's host.
+    The status is `None` if the upstream couldn't be reached, labeled as `error`.
+    */
+    pub fn observe_upstream(host: &str, method: &str, status: Option<u16>, duration: Duration) {
+        if let Some(metrics) = METRICS.as_ref() {
+            let status = status.map_or_else(|| String::from("error"), |status| status.to_string());
+            metrics
+                .upstream_duration
+                .with_label_values(&[host, method_label(method), &status])
+                .observe(duration.as_secs_f64());
+        }
+    }
+
+    pub fn observe_database(backend: &str, table: &str, operation: &str, duration: Duration) {
+        if let Some(metrics) = METRICS.as_ref() {
+            metrics
+                .database_duration
+                .with_label_values(&[backend, table, operation])
+                .observe(duration.as_secs_f64());
+        }
+    }
+
+    /**
+    Export the metrics in the Prometheus text format.
+    */
+    pub fn export() -> prometheus::Result<String> {
+        let Some(metrics) = METRICS.as_ref() else {
+            return Ok(String::new());
+        };
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&metrics.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|e| prometheus::Error::Msg(e.to_string()))
+    }
+}
+
+// Functions
+/**
+Get the label of a client's method. The unknown methods share the `other` label, so they can't grow the metrics.
+*/
+fn method_label(method: &str) -> &'static str {
+    HTTP_METHODS
+        .into_iter()
+        .find(|known| known.eq_ignore_ascii_case(method))
+        .unwrap_or("other")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_the_unknown_methods_as_other() {
+        assert_eq!(method_label("GET"), "GET");
+        assert_eq!(method_label("patch"), "PATCH");
+        assert_eq!(method_label("PROPFIND"), "other");
+        assert_eq!(method_label("X-RANDOM-1234"), "other");
+    }
+}
@@ -1,17 +1,17 @@
 // Libs
-use super::DATABASE;
+use super::{BootstrapService, DatabaseService, Repository};
 use crate::{
     errors::DBResult,
-    schemas::{Identity, Model, Policy},
+    schemas::{Identity, Policy},
     traits::{Database, ModelProperties},
     utils::{
-        get_default_identity_policies, get_default_master_identity, get_default_policy_policies,
-        get_default_poliwarden_identity,
+        get_default_master_identity, get_default_poliwarden_identity, Secret, DEFAULT_SETTINGS,
     },
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
+use ulid::Ulid;
 
 // Structs
 pub struct Migrations {
@@ -20,39 +20,42 @@ pub struct Migrations {
 
 // Implementations
 impl Migrations {
-    pub fn new() -> Self {
-        Self {
-            db: DATABASE.get().unwrap().clone(),
-        }
+    pub fn new() -> DBResult<Self> {
+        Ok(Self {
+            db: DatabaseService::get_database()?,
+        })
     }
 
+    /**
+    Run the migrations in a transaction, so a failure never leaves a half-seeded database.
+    The generated master bearer is only stored once the transaction is committed.
+    */
     pub async fn run(&self) -> DBResult<()> {
         info!("Running the migration scripts...");
 
-        self.init_tables().await?;
-        let (poliw_identity, master_identity) = self.init_identities().await?;
-        self.init_policies(&poliw_identity, &master_identity)
-            .await?;
+        let db = self.db.write().await;
+        let bootstrap = DatabaseService::transaction(db.as_ref(), self.seed(db.as_ref())).await?;
+        if let Some((identity_id, bearer)) = bootstrap {
+            BootstrapService::store(&identity_id, &bearer)?;
+        }
 
         info!("Migration scripts successfully executed.");
         Ok(())
     }
 
+    async fn seed(&self, db: &dyn Database) -> DBResult<Option<(String, Secret<String>)>> {
+        self.init_tables(db).await?;
+        self.init_identities(db).await
+    }
+
     /**
     Initialize the database tables.
     */
-    async fn init_tables(&self) -> DBResult<()> {
+    async fn init_tables(&self, db: &dyn Database) -> DBResult<()> {
         info!("Initializing the database tables...");
-        let db_name = self.db.read().await.get_database_name();
-
-        let policy_model: Model = Policy::default().into();
-        let identity_model: Model = Identity::default().into();
 
-        let policy_script = policy_model.get_migration_script(&db_name);
-        let identity_script = identity_model.get_migration_script(&db_name);
-
-        self.db.write().await.query(&policy_script).await?;
-        self.db.write().await.query(&identity_script).await?;
+        Repository::<Policy>::new(db).create_table().await?;
+        Repository::<Identity>::new(db).create_table().await?;
 
         info!("Database tables initialized.");
         Ok(())
@@ -60,37 +63,33 @@ impl Migrations {
 
     /**
     Initialize the default identities.
-    Returns the PoliWarden and master identities respectively.
+    Their policies are built in and reconciled on every start.
+    Without any master credentials, a bearer is generated and returned with the master's id.
     */
-    async fn init_identities(&self) -> DBResult<(Model, Model)> {
+    async fn init_identities(
+        &self,
+        db: &dyn Database,
+    ) -> DBResult<Option<(String, Secret<String>)>> {
         info!("Initializing the master identity...");
 
-        let poliw_identity: Model = get_default_poliwarden_identity().into();
-        let poliw_table_name = poliw_identity.get_table_name();
-        let master_identity: Model = get_default_master_identity()?.into();
-        let master_table_name = master_identity.get_table_name();
+        let settings = DEFAULT_SETTINGS.load();
+        let generated_bearer = match (&settings.master_bearer, &settings.master_cert) {
+            (None, None) => Some(Secret::new(Ulid::new().to_string())),
+            _ => None,
+        };
+        let master_bearer = settings
+            .master_bearer
+            .as_ref()
+            .or(generated_bearer.as_ref());
 
-        let db = self.db.write().await;
-        db.insert(&poliw_table_name, &poliw_identity).await?;
-        db.insert(&master_table_name, &master_identity).await?;
+        let poliw_identity = get_default_poliwarden_identity()?;
+        let master_identity = get_default_master_identity(master_bearer)?;
 
-        info!("Master identity initialized.");
-        Ok((poliw_identity, master_identity))
-    }
-
-    async fn init_policies(&self, poliw_model: &Model, master_model: &Model) -> DBResult<()> {
-        info!("Initializing the master policy...");
+        let identities = Repository::<Identity>::new(db);
+        identities.insert(&poliw_identity).await?;
+        identities.insert(&master_identity).await?;
 
-        let mut default_policies = get_default_policy_policies(poliw_model, master_model);
-        default_policies.extend(get_default_identity_policies(poliw_model, master_model));
-        let default_policies: Vec<Model> = default_policies.into_iter().map(|p| p.into()).collect();
-
-        let db = self.db.write().await;
-        for policy in default_policies {
-            db.insert(&policy.get_table_name(), &policy).await?;
-        }
-
-        info!("Master policy initialized.");
-        Ok(())
+        info!("Master identity initialized.");
+        Ok(generated_bearer.map(|bearer| (master_identity.get_id(), bearer)))
     }
 }
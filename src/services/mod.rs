@@ -1,9 +1,31 @@
+pub use audit::*;
+pub use bootstrap::*;
+pub use change::*;
 pub use database::*;
-pub use identity::*;
+pub use health::*;
+pub use lint::*;
+pub use metrics::*;
 pub use migrations::*;
+pub use policy_file::*;
+pub use reload::*;
+pub use repository::*;
+pub use store_migration::*;
 pub use validators::*;
 
+pub mod audit;
+pub mod authorization;
+pub mod bootstrap;
+pub mod bundle;
+pub mod change;
 pub mod database;
+pub mod health;
 pub mod identity;
+pub mod lint;
+pub mod metrics;
 pub mod migrations;
+pub mod policy;
+pub mod policy_file;
+pub mod reload;
+pub mod repository;
+pub mod store_migration;
 pub mod validators;
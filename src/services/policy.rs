@@ -0,0 +1,197 @@
+// Libs
+use super::{
+    authorization::AuthorizationService, ChangeService, DatabaseService, LintService, Repository,
+};
+use crate::{
+    errors::{AppError, AppResult, DatabaseError},
+    schemas::{ChangeAction, LintedPolicy, Policy, PolicyIn, PolicyOut, QueryOptions},
+};
+use tracing::warn;
+
+// Structs
+/**
+The management operations of the policies.
+*/
+pub struct PolicyService;
+
+// Implementations
+impl PolicyService {
+    pub async fn get_all(options: &QueryOptions) -> AppResult<Vec<PolicyOut>> {
+        options.validate()?;
+
+        let db = DatabaseService::get_database()?;
+        let db = db.read().await;
+        let policies = Repository::<Policy>::new(db.as_ref())
+            .get_all(options)
+            .await?;
+        Ok(policies.into_iter().map(PolicyOut::from).collect())
+    }
+
+    /**
+    Create a policy, recording the change by the actor.
+    It's linted against the identity's policies, and the warnings are returned with it.
+    */
+    pub async fn create(policy: PolicyIn, actor: &str) -> AppResult<LintedPolicy> {
+        let policy = Policy::from(policy);
+
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let policies = Repository::<Policy>::new(db);
+
+        let identity_policies = policies
+            .find_by("identity_id", policy.get_identity_id())
+            .await?;
+        let identity_ids = LintService::get_identity_ids(db).await?;
+        let warnings = LintService::lint(&policy, &identity_policies, &identity_ids);
+
+        DatabaseService::transaction(db, async {
+            policies.insert(&policy).await?;
+            ChangeService::record(db, actor, ChangeAction::Create, None, Some(&policy)).await
+        })
+        .await?;
+
+        for warning in &warnings {
+            warn!(
+                policy_id = warning.policy_id,
+                rule = ?warning.rule,
+                warning = warning.message,
+                "The policy was created with a warning."
+            );
+        }
+        Ok(LintedPolicy {
+            policy: policy.into(),
+            warnings,
+        })
+    }
+
+    /**
+    Update a policy, recording the change by the actor.
+    The revision is the one the client has seen, so concurrent updates aren't lost.
+    */
+    pub async fn update(
+        id: &str,
+        policy: PolicyIn,
+        revision: u64,
+        actor: &str,
+    ) -> AppResult<PolicyOut> {
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let policies = Repository::<Policy>::new(db);
+
+        let Some(stored_policy) = policies.get(id).await? else {
+            return Err(not_found(id));
+        };
+        let mut policy_update = stored_policy.clone();
+        policy_update.update_from(policy);
+
+        let updated = DatabaseService::transaction(db, async {
+            let Some(updated) = policies.update(&policy_update, Some(revision)).await? else {
+                return Ok(None);
+            };
+            ChangeService::record(
+                db,
+                actor,
+                ChangeAction::Update,
+                Some(&stored_policy),
+                Some(&updated),
+            )
+            .await?;
+            Ok::<_, DatabaseError>(Some(updated))
+        })
+        .await?;
+
+        match updated {
+            Some(policy) => Ok(policy.into()),
+            None => Err(not_found(id)),
+        }
+    }
+
+    /**
+    Delete a policy, recording the change by the actor.
+    */
+    pub async fn delete(id: &str, actor: &str) -> AppResult<()> {
+        let db = DatabaseService::get_database()?;
+        let db = db.write().await;
+        let db = db.as_ref();
+        let policies = Repository::<Policy>::new(db);
+
+        let Some(stored_policy) = policies.get(id).await? else {
+            return Err(not_found(id));
+        };
+        DatabaseService::transaction(db, async {
+            policies.delete(id).await?;
+            ChangeService::record(db, actor, ChangeAction::Delete, Some(&stored_policy), None).await
+        })
+        .await?;
+        AuthorizationService::forget_policy(id);
+        Ok(())
+    }
+}
+
+// Functions
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("The policy {id} doesn't exist."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        schemas::{IdentityIn, PathSyntax},
+        services::identity::IdentityService,
+        traits::ModelProperties,
+    };
+    use ulid::Ulid;
+
+    fn policy_in(identity_id: &str, method: &str) -> PolicyIn {
+        PolicyIn::new(
+            String::from("/orders/**"),
+            PathSyntax::Glob,
+            method.to_string(),
+            identity_id.to_string(),
+            identity_id.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn records_the_changes_by_the_actor() {
+        DatabaseService::init_temporary().await;
+        let identity = IdentityIn::new(
+            format!("identity-{}", Ulid::new()),
+            String::from("orders.local"),
+            None,
+            None,
+        );
+        let identity_id = IdentityService::create(identity, "creator")
+            .await
+            .unwrap()
+            .get_id();
+
+        PolicyService::create(policy_in(&identity_id, "^GET$"), "creator")
+            .await
+            .unwrap();
+        let db = DatabaseService::get_database().unwrap();
+        let policies = Repository::<Policy>::new(db.read().await.as_ref())
+            .find_by("identity_id", &identity_id)
+            .await
+            .unwrap();
+        let id = policies[0].get_id();
+
+        PolicyService::update(&id, policy_in(&identity_id, "^POST$"), 1, "updater")
+            .await
+            .unwrap();
+        PolicyService::delete(&id, "deleter").await.unwrap();
+
+        let history = ChangeService::get_history(db.read().await.as_ref(), &id).await;
+        assert_eq!(
+            history,
+            [
+                (ChangeAction::Create, String::from("creator")),
+                (ChangeAction::Update, String::from("updater")),
+                (ChangeAction::Delete, String::from("deleter")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,402 @@
+// Libs
+use super::{authorization::AuthorizationService, ChangeService, DatabaseService, Repository};
+use crate::{
+    errors::{DBResult, DatabaseError},
+    schemas::{
+        ChangeAction, DeclaredPolicy, Identity, IdentityIn, Policy, PolicyDocument, PolicyIn,
+        QueryOptions, ReconcileReport,
+    },
+    traits::{Database, ModelProperties, Tracked},
+    utils::DEFAULT_SETTINGS,
+};
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tracing::{info, warn};
+
+// Data
+/**
+The source of the built-in policies. Its records are always pruned.
+*/
+pub const BUILTIN_SOURCE: &str = "builtin";
+
+/**
+The source of the policy file defined by `POLIWARDEN_POLICY_FILE`.
+*/
+pub const POLICY_FILE_SOURCE: &str = "policy-file";
+
+/**
+Set once the documents are reconciled. A failed reload keeps the previous policies, so it's never unset.
+*/
+static RECONCILED: AtomicBool = AtomicBool::new(false);
+
+// Structs
+/**
+Reconcile the declarative policy documents into the database.
+The records created by a document are marked as managed by its source, so they can be updated and pruned later.
+*/
+pub struct PolicyFileService;
+
+// Implementations
+impl PolicyFileService {
+    /**
+    Reconcile the built-in document and the policy file, if defined, in a single transaction.
+    */
+    pub async fn reconcile_all(db: &dyn Database) -> DBResult<Vec<ReconcileReport>> {
+        let builtin = PolicyDocument::builtin()?;
+        let policy_file = match &DEFAULT_SETTINGS.load().policy_file {
+            None => None,
+            Some(path) => Some(PolicyDocument::load(path)?),
+        };
+
+        let reports = DatabaseService::transaction(db, async {
+            let mut reports = vec![Self::reconcile(db, &builtin, BUILTIN_SOURCE, true).await?];
+            if let Some(document) = &policy_file {
+                reports
+                    .push(Self::reconcile(db, document, POLICY_FILE_SOURCE, document.prune).await?);
+            }
+            Ok(reports)
+        })
+        .await?;
+
+        RECONCILED.store(true, Ordering::Relaxed);
+        Ok(reports)
+    }
+
+    /**
+    Check if the policy documents were reconciled since the start.
+    */
+    pub fn is_reconciled() -> bool {
+        RECONCILED.load(Ordering::Relaxed)
+    }
+
+    /**
+    Create and update the document's records, adopting the equal unmanaged policies.
+    The unmanaged identities aren't adopted, so a declared identity can't take one over.
+    With `prune`, the records of the source that aren't in the document are deleted.
+    The changes are recorded with the source as their actor.
+    */
+    pub async fn reconcile(
+        db: &dyn Database,
+        document: &PolicyDocument,
+        source: &str,
+        prune: bool,
+    ) -> DBResult<ReconcileReport> {
+        info!(source, "Reconciling the policy document...");
+
+        let identities = Repository::<Identity>::new(db);
+        let policies = Repository::<Policy>::new(db);
+        let mut report = ReconcileReport {
+            source: source.to_string(),
+            ..Default::default()
+        };
+
+        let mut all_identities = identities.get_all(&QueryOptions::new()).await?;
+        for declared in &document.identities {
+            let identity_in =
+                IdentityIn::new(declared.name.clone(), declared.host.clone(), None, None);
+            match find_identity(&all_identities, &declared.name)? {
+                None => {
+                    let mut identity = Identity::try_from(identity_in)?;
+                    identity.set_managed_by(source);
+                    identities.insert(&identity).await?;
+                    ChangeService::record(db, source, ChangeAction::Create, None, Some(&identity))
+                        .await?;
+                    all_identities.push(identity);
+                    report.created_identities += 1;
+                }
+                Some(index) => {
+                    // The other identities are never adopted, so they can't be pruned either.
+                    let identity = &mut all_identities[index];
+                    match identity.get_managed_by() {
+                        Some(managed_by) if managed_by == source => {}
+                        Some(other_source) => {
+                            return Err(DatabaseError::Conflict(format!(
+                                "The identity {} is managed by {other_source}.",
+                                declared.name
+                            )))
+                        }
+                        None => {
+                            return Err(DatabaseError::Conflict(format!(
+                                "The identity {} already exists and isn't managed by {source}.",
+                                declared.name
+                            )))
+                        }
+                    }
+                    if identity.get_host() != declared.host {
+                        let before = identity.clone();
+                        identity.update_from(identity_in)?;
+                        if let Some(updated) = identities.update(identity, None).await? {
+                            *identity = updated;
+                        }
+                        record_update(db, source, &before, identity).await?;
+                        report.updated_identities += 1;
+                    }
+                }
+            }
+        }
+
+        let mut all_policies = policies.get_all(&QueryOptions::new()).await?;
+        let mut declared_ids = HashSet::new();
+        for declared in &document.policies {
+            let policy_in = resolve_policy(&all_identities, declared)?;
+            let managed = all_policies.iter().position(|policy| {
+                policy.get_managed_by() == Some(source)
+                    && policy.get_name() == Some(declared.name.as_str())
+            });
+            let adoptable = || {
+                all_policies.iter().position(|policy| {
+                    policy.get_managed_by().is_none() && policy.matches(&policy_in)
+                })
+            };
+
+            match managed.or_else(adoptable) {
+                None => {
+                    let mut policy = Policy::from(policy_in);
+                    policy.set_managed_by(source, &declared.name);
+                    policies.insert(&policy).await?;
+                    ChangeService::record(db, source, ChangeAction::Create, None, Some(&policy))
+                        .await?;
+                    declared_ids.insert(policy.get_id());
+                    all_policies.push(policy);
+                    report.created_policies += 1;
+                }
+                Some(index) => {
+                    let policy = &mut all_policies[index];
+                    let before = policy.clone();
+                    declared_ids.insert(policy.get_id());
+                    if policy.get_managed_by().is_none() {
+                        policy.set_managed_by(source, &declared.name);
+                        if let Some(updated) = policies.update(policy, None).await? {
+                            *policy = updated;
+                        }
+                        record_update(db, source, &before, policy).await?;
+                        report.adopted_policies += 1;
+                    } else if !policy.matches(&policy_in) {
+                        policy.update_from(policy_in);
+                        if let Some(updated) = policies.update(policy, None).await? {
+                            *policy = updated;
+                        }
+                        record_update(db, source, &before, policy).await?;
+                        report.updated_policies += 1;
+                    }
+                }
+            }
+        }
+
+        if prune {
+            for policy in &all_policies {
+                if policy.get_managed_by() == Some(source)
+                    && !declared_ids.contains(&policy.get_id())
+                {
+                    policies.delete(&policy.get_id()).await?;
+                    AuthorizationService::forget_policy(&policy.get_id());
+                    ChangeService::record(db, source, ChangeAction::Delete, Some(policy), None)
+                        .await?;
+                    report.deleted_policies += 1;
+                }
+            }
+
+            let declared_names: HashSet<&str> = document
+                .identities
+                .iter()
+                .map(|identity| identity.name.as_str())
+                .collect();
+            for identity in &all_identities {
+                if identity.get_managed_by() == Some(source)
+                    && !declared_names.contains(identity.get_name())
+                {
+                    // Like the deletions of the command line, the policies must never lose their identity.
+                    if has_policies(&policies, &identity.get_id()).await? {
+                        warn!(
+                            source,
+                            identity = identity.get_name(),
+                            "The undeclared identity still holds or owns policies, so it's kept."
+                        );
+                        report.kept_identities.push(identity.get_name().to_string());
+                        continue;
+                    }
+                    identities.delete(&identity.get_id()).await?;
+                    ChangeService::record(db, source, ChangeAction::Delete, Some(identity), None)
+                        .await?;
+                    report.deleted_identities += 1;
+                }
+            }
+        }
+
+        info!(
+            source,
+            created_identities = report.created_identities,
+            updated_identities = report.updated_identities,
+            deleted_identities = report.deleted_identities,
+            created_policies = report.created_policies,
+            updated_policies = report.updated_policies,
+            adopted_policies = report.adopted_policies,
+            deleted_policies = report.deleted_policies,
+            kept_identities = ?report.kept_identities,
+            "Policy document reconciled."
+        );
+        Ok(report)
+    }
+}
+
+// Functions
+async fn record_update<T: Tracked>(
+    db: &dyn Database,
+    source: &str,
+    before: &T,
+    after: &T,
+) -> DBResult<()> {
+    ChangeService::record(db, source, ChangeAction::Update, Some(before), Some(after)).await
+}
+
+/**
+Check if the identity holds or owns any policy.
+*/
+async fn has_policies(policies: &Repository<'_, Policy>, identity_id: &str) -> DBResult<bool> {
+    Ok(!policies
+        .find_by("identity_id", identity_id)
+        .await?
+        .is_empty()
+        || !policies.find_by("owner_id", identity_id).await?.is_empty())
+}
+
+/**
+Find the index of the identity with the name, failing if the name is ambiguous.
+*/
+fn find_identity(identities: &[Identity], name: &str) -> DBResult<Option<usize>> {
+    let mut matches = identities
+        .iter()
+        .enumerate()
+        .filter(|(_, identity)| identity.get_name() == name)
+        .map(|(index, _)| index);
+
+    match (matches.next(), matches.next()) {
+        (None, _) => Ok(None),
+        (Some(index), None) => Ok(Some(index)),
+        (Some(_), Some(_)) => Err(DatabaseError::Conflict(format!(
+            "More than one identity is named {name}."
+        ))),
+    }
+}
+
+/**
+Resolve the identity names of the declared policy into their ids.
+*/
+fn resolve_policy(identities: &[Identity], declared: &DeclaredPolicy) -> DBResult<PolicyIn> {
+    let resolve = |name: &str| match find_identity(identities, name)? {
+        Some(index) => Ok(identities[index].get_id()),
+        None => Err(DatabaseError::InvalidField(format!(
+            "The policy {} references the unknown identity {name}.",
+            declared.name
+        ))),
+    };
+
+    Ok(PolicyIn::new(
+        declared.path.clone(),
+        declared.path_syntax,
+        declared.method.clone(),
+        resolve(&declared.owner)?,
+        resolve(&declared.identity)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{databases::FileDatabase, schemas::PolicyDocumentFormat};
+
+    fn document(host: &str, method: &str) -> PolicyDocument {
+        let content = format!(
+            "
+            identities:
+              - name: billing
+                host: {host}
+            policies:
+              - name: billing-read
+                identity: billing
+                owner: billing
+                path: '^/invoices$'
+                method: '{method}'
+            "
+        );
+        PolicyDocument::parse(&content, PolicyDocumentFormat::Yaml).unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_the_changes_by_the_source() {
+        let (_directory, db) = FileDatabase::temporary().await;
+        let first = document("a.local", "^GET$");
+        let second = document("b.local", "^POST$");
+        let empty = PolicyDocument::parse("prune: true", PolicyDocumentFormat::Yaml).unwrap();
+
+        PolicyFileService::reconcile(&db, &first, POLICY_FILE_SOURCE, true)
+            .await
+            .unwrap();
+        let identity = Repository::<Identity>::new(&db)
+            .find_by("name", "billing")
+            .await
+            .unwrap()
+            .remove(0);
+        let policy = Repository::<Policy>::new(&db)
+            .find_by("identity_id", &identity.get_id())
+            .await
+            .unwrap()
+            .remove(0);
+
+        for document in [second, empty] {
+            PolicyFileService::reconcile(&db, &document, POLICY_FILE_SOURCE, true)
+                .await
+                .unwrap();
+        }
+
+        let source = String::from(POLICY_FILE_SOURCE);
+        let history = [
+            (ChangeAction::Create, source.clone()),
+            (ChangeAction::Update, source.clone()),
+            (ChangeAction::Delete, source),
+        ];
+        for id in [identity.get_id(), policy.get_id()] {
+            assert_eq!(ChangeService::get_history(&db, &id).await, history);
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_the_undeclared_identities_with_policies() {
+        let (_directory, db) = FileDatabase::temporary().await;
+        let first = document("a.local", "^GET$");
+        PolicyFileService::reconcile(&db, &first, POLICY_FILE_SOURCE, true)
+            .await
+            .unwrap();
+        let identity_id = Repository::<Identity>::new(&db)
+            .find_by("name", "billing")
+            .await
+            .unwrap()
+            .remove(0)
+            .get_id();
+        // An unmanaged policy, added apart from the document, still points to the identity.
+        let policy = Policy::from(PolicyIn::new(
+            String::from("^/reports$"),
+            Default::default(),
+            String::from("^GET$"),
+            identity_id.clone(),
+            identity_id.clone(),
+        ));
+        Repository::<Policy>::new(&db)
+            .insert(&policy)
+            .await
+            .unwrap();
+
+        let empty = PolicyDocument::parse("prune: true", PolicyDocumentFormat::Yaml).unwrap();
+        let report = PolicyFileService::reconcile(&db, &empty, POLICY_FILE_SOURCE, true)
+            .await
+            .unwrap();
+
+        assert_eq!(report.deleted_policies, 1);
+        assert_eq!(report.deleted_identities, 0);
+        assert_eq!(report.kept_identities, ["billing"]);
+        let identity = Repository::<Identity>::new(&db).get(&identity_id).await;
+        assert!(identity.unwrap().is_some());
+    }
+}
@@ -0,0 +1,162 @@
+// Libs
+use super::{DatabaseService, EnvValidator, PolicyFileService};
+use crate::{
+    errors::DBResult,
+    utils::{set_log_level, ConfigFile, CONFIG_FILE, DEFAULT_SETTINGS},
+};
+use std::{fs::metadata, path::PathBuf, sync::Arc, time::Duration, time::SystemTime};
+use tokio::time::interval;
+use tracing::{error, info};
+
+// Data
+/**
+How often the policy file is checked for changes.
+*/
+const POLICY_FILE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// Types
+/**
+The policy file and its last modification time, used to detect changes.
+*/
+type PolicyFileVersion = Option<(PathBuf, Option<SystemTime>)>;
+
+// Structs
+/**
+Reload the settings and the policy file while the application is running.
+The settings are swapped atomically, so the readers keep using a consistent snapshot until they load it again.
+*/
+pub struct ReloadService {
+    policy_file_version: PolicyFileVersion,
+}
+
+// Implementations
+impl ReloadService {
+    /**
+    Create the service before the policies are first loaded, so the changes made meanwhile aren't missed.
+    */
+    pub fn new() -> Self {
+        Self {
+            policy_file_version: get_policy_file_version(),
+        }
+    }
+
+    /**
+    Reload everything on SIGHUP, and the policies when the policy file changes.
+    A failed reload is logged and the previous settings and policies are kept.
+    */
+    pub async fn watch(mut self) {
+        #[cfg(unix)]
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(hangup) => Some(hangup),
+            Err(e) => {
+                error!(error = %e, "Couldn't listen to SIGHUP.");
+                None
+            }
+        };
+        #[cfg(not(unix))]
+        let mut hangup = None::<()>;
+
+        let mut poll = interval(POLICY_FILE_POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = poll.tick() => {
+                    let version = get_policy_file_version();
+                    if version != self.policy_file_version {
+                        info!("Policy file changed, reloading...");
+                        self.policy_file_version = version;
+                        Self::reload_policies().await;
+                    }
+                }
+                _ = hangup_received(&mut hangup) => {
+                    info!("SIGHUP received, reloading...");
+                    Self::reload().await;
+                    self.policy_file_version = get_policy_file_version();
+                }
+            }
+        }
+    }
+
+    /**
+    Reload the settings, the log level and the policies.
+    */
+    pub async fn reload() {
+        if let Err(errors) = Self::reload_settings() {
+            for e in &errors {
+                error!(error = e.as_str(), "Couldn't reload the settings.");
+            }
+            return;
+        }
+        Self::reload_policies().await;
+    }
+
+    /**
+    Read the configuration file again and validate the reloadable settings.
+    They're swapped only if all of them are valid, otherwise the previous configuration file is restored.
+    */
+    fn reload_settings() -> Result<(), Vec<String>> {
+        let (config_file, errors) = ConfigFile::load();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let previous_config_file = CONFIG_FILE.swap(Arc::new(config_file));
+
+        let validated = match EnvValidator::validate_log_level() {
+            Ok(log_level) => DEFAULT_SETTINGS
+                .load()
+                .reload()
+                .map(|settings| (log_level, settings)),
+            Err(e) => Err(vec![e]),
+        };
+        let (log_level, settings) = validated.inspect_err(|_| {
+            CONFIG_FILE.store(previous_config_file);
+        })?;
+
+        set_log_level(log_level).map_err(|e| vec![e])?;
+        DEFAULT_SETTINGS.store(Arc::new(settings));
+
+        info!("Settings reloaded.");
+        Ok(())
+    }
+
+    async fn reload_policies() {
+        let result: DBResult<()> = async {
+            let db = DatabaseService::get_database()?;
+            let db = db.write().await;
+            PolicyFileService::reconcile_all(db.as_ref()).await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            error!(error = %e, "Couldn't reload the policies.");
+        }
+    }
+}
+
+// Functions
+fn get_policy_file_version() -> PolicyFileVersion {
+    let policy_file = DEFAULT_SETTINGS.load().policy_file.clone()?;
+    let modified = metadata(&policy_file)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    Some((policy_file, modified))
+}
+
+/**
+Wait for a SIGHUP. Without a listener, it waits forever.
+*/
+#[cfg(unix)]
+async fn hangup_received(hangup: &mut Option<tokio::signal::unix::Signal>) {
+    match hangup {
+        Some(hangup) => {
+            hangup.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(not(unix))]
+async fn hangup_received(_hangup: &mut Option<()>) {
+    std::future::pending().await
+}
@@ -0,0 +1,91 @@
+// Libs
+use crate::{
+    errors::DBResult,
+    schemas::QueryOptions,
+    traits::{Database, ModelProperties, SerdeModel},
+};
+use std::marker::PhantomData;
+
+// Structs
+/**
+A typed access to the table of an entity.
+It borrows the database, so the caller decides which lock is held while using it.
+*/
+pub struct Repository<'a, T> {
+    db: &'a dyn Database,
+    model: PhantomData<fn() -> T>,
+}
+
+// Implementations
+impl<'a, T: ModelProperties + SerdeModel> Repository<'a, T> {
+    pub fn new(db: &'a dyn Database) -> Self {
+        Self {
+            db,
+            model: PhantomData,
+        }
+    }
+
+    /**
+    Create the entity's table.
+    */
+    pub async fn create_table(&self) -> DBResult<()> {
+        self.db.create_table(&T::table_name()).await
+    }
+
+    /**
+    Get a single entity by its id.
+    */
+    pub async fn get(&self, id: &str) -> DBResult<Option<T>> {
+        match self.db.get(&T::table_name(), id).await? {
+            None => Ok(None),
+            Some(model) => Ok(Some(model.try_into()?)),
+        }
+    }
+
+    /**
+    Get the entities filtered, sorted and paginated by the options.
+    */
+    pub async fn get_all(&self, options: &QueryOptions) -> DBResult<Vec<T>> {
+        let models = self.db.get_all(&T::table_name(), options).await?;
+        models.into_iter().map(T::try_from).collect()
+    }
+
+    /**
+    Get the entities whose field is equal to the value.
+    */
+    pub async fn find_by(&self, field: &str, value: &str) -> DBResult<Vec<T>> {
+        let models = self.db.find_by(&T::table_name(), field, value).await?;
+        models.into_iter().map(T::try_from).collect()
+    }
+
+    /**
+    Count the entities matching the options' filters.
+    */
+    pub async fn count(&self, options: &QueryOptions) -> DBResult<usize> {
+        self.db.count(&T::table_name(), options).await
+    }
+
+    pub async fn insert(&self, data: &T) -> DBResult<()> {
+        self.db.insert(&T::table_name(), &data.clone().into()).await
+    }
+
+    /**
+    Update an entity, returning it with its new revision.
+    If the expected revision is defined, the entity's stored revision must match it.
+    */
+    pub async fn update(&self, data: &T, expected_revision: Option<u64>) -> DBResult<Option<T>> {
+        let model = data.clone().into();
+        match self
+            .db
+            .update(&T::table_name(), &model, expected_revision)
+            .await?
+        {
+            None => Ok(None),
+            Some(model) => Ok(Some(model.try_into()?)),
+        }
+    }
+
+    pub async fn delete(&self, id: &str) -> DBResult<Option<()>> {
+        self.db.delete(&T::table_name(), id).await
+    }
+}
@@ -0,0 +1,223 @@
+// Libs
+use super::DatabaseService;
+use crate::{
+    errors::{DBResult, DatabaseError},
+    schemas::{Model, QueryOptions, StoreMigrationReport, TableMigrationReport},
+    traits::{Database, ModelProperties},
+};
+use std::collections::BTreeSet;
+use tracing::{info, Level};
+use ulid::Ulid;
+
+// Data
+/**
+The amount of records read at once from a table.
+*/
+const PAGE_SIZE: usize = 500;
+
+// Structs
+/**
+Copy all the records of a store to another, possibly of a different backend.
+*/
+pub struct StoreMigrationService;
+
+// Implementations
+impl StoreMigrationService {
+    /**
+    Copy the records, skipping the ids the target already has, and verify the target afterwards.
+    The source is only read, so it's left as it was found.
+    */
+    pub async fn migrate(
+        source: &dyn Database,
+        target: &dyn Database,
+    ) -> DBResult<StoreMigrationReport> {
+        info!("Migrating the store...");
+
+        source.connect_read_only().await?;
+        target.connect().await?;
+        if source.is_first_run().await? {
+            return Err(DatabaseError::InvalidField(String::from(
+                "The source store isn't initialized.",
+            )));
+        }
+
+        let mut report = StoreMigrationReport {
+            from: source.get_database_name(),
+            to: target.get_database_name(),
+            tables: Vec::new(),
+        };
+        for table_name in Model::get_table_names() {
+            report
+                .tables
+                .push(Self::migrate_table(source, target, &table_name).await?);
+        }
+
+        info!(is_consistent = report.is_consistent(), "Store migrated.");
+        Ok(report)
+    }
+
+    async fn migrate_table(
+        source: &dyn Database,
+        target: &dyn Database,
+        table_name: &str,
+    ) -> DBResult<TableMigrationReport> {
+        let span = tracing::span!(Level::INFO, "", table = table_name);
+        let _enter = span.enter();
+        info!("Migrating table...");
+
+        let mut report = TableMigrationReport {
+            table: table_name.to_string(),
+            ..Default::default()
+        };
+        target.create_table(table_name).await?;
+        let existing_ids = Self::get_ids(target, table_name).await?;
+
+        let mut cursor = None;
+        loop {
+            let page = Self::get_page(source, table_name, cursor).await?;
+            let missing: Vec<Model> = page
+                .iter()
+                .filter(|model| !existing_ids.contains(&model.get_id()))
+                .cloned()
+                .collect();
+            report.already_present += page.len() - missing.len();
+
+            // Each page is written at once, so it's either fully copied or not at all.
+            if !missing.is_empty() {
+                let insert = target.insert_many(table_name, &missing);
+                DatabaseService::transaction(target, insert).await?;
+                report.copied += missing.len();
+            }
+
+            match page.last() {
+                Some(model) if page.len() == PAGE_SIZE => {
+                    cursor = Some(Ulid::from_string(&model.get_id())?)
+                }
+                _ => break,
+            }
+        }
+
+        let source_ids = Self::get_ids(source, table_name).await?;
+        let target_ids = Self::get_ids(target, table_name).await?;
+        report.source_count = source_ids.len();
+        report.target_count = target_ids.len();
+        report.missing_ids = source_ids.difference(&target_ids).cloned().collect();
+        report.unexpected_ids = target_ids.difference(&source_ids).cloned().collect();
+
+        info!(
+            copied = report.copied,
+            already_present = report.already_present,
+            "Table migrated."
+        );
+        Ok(report)
+    }
+
+    async fn get_page(
+        db: &dyn Database,
+        table_name: &str,
+        cursor: Option<Ulid>,
+    ) -> DBResult<Vec<Model>> {
+        let mut options = QueryOptions::new().limit(PAGE_SIZE);
+        if let Some(cursor) = cursor {
+            options = options.cursor(cursor);
+        }
+        db.get_all(table_name, &options).await
+    }
+
+    async fn get_ids(db: &dyn Database, table_name: &str) -> DBResult<BTreeSet<String>> {
+        let mut ids = BTreeSet::new();
+        let mut cursor = None;
+        loop {
+            let page = Self::get_page(db, table_name, cursor).await?;
+            ids.extend(page.iter().map(|model| model.get_id()));
+
+            match page.last() {
+                Some(model) if page.len() == PAGE_SIZE => {
+                    cursor = Some(Ulid::from_string(&model.get_id())?)
+                }
+                _ => return Ok(ids),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        databases::FileDatabase,
+        schemas::{ChangeRecord, Identity, IdentityIn, Policy},
+        traits::SerdeModel,
+    };
+    use std::fs::read;
+
+    const KEY: [u8; 32] = [1; 32];
+
+    fn identity(name: &str) -> Model {
+        let identity = IdentityIn::new(name.to_string(), String::from("a.local"), None, None);
+        Model::Identity(Identity::try_from(identity).unwrap())
+    }
+
+    fn table_report<'a>(report: &'a StoreMigrationReport, table: &str) -> &'a TableMigrationReport {
+        report.tables.iter().find(|t| t.table == table).unwrap()
+    }
+
+    #[tokio::test]
+    async fn copies_the_records_without_changing_the_source() {
+        let source_directory = tempfile::tempdir().unwrap();
+        let target_directory = tempfile::tempdir().unwrap();
+        // A store from before the change history, so it has no change table.
+        {
+            let db = FileDatabase::new(source_directory.path());
+            db.connect().await.unwrap();
+            db.create_table(&Identity::table_name()).await.unwrap();
+            db.create_table(&Policy::table_name()).await.unwrap();
+            db.insert(&Identity::table_name(), &identity("billing"))
+                .await
+                .unwrap();
+        }
+        let identity_path = source_directory.path().join("identity.json");
+        let source_content = read(&identity_path).unwrap();
+
+        // The key would encrypt the plaintext source on a regular connection.
+        let source = FileDatabase::new(source_directory.path()).with_encryption(Some(KEY), None);
+        let target = FileDatabase::new(target_directory.path()).with_encryption(Some(KEY), None);
+        let report = StoreMigrationService::migrate(&source, &target)
+            .await
+            .unwrap();
+
+        assert!(report.is_consistent());
+        assert_eq!(table_report(&report, &Identity::table_name()).copied, 1);
+        assert_eq!(
+            table_report(&report, &ChangeRecord::table_name()).source_count,
+            0
+        );
+        assert_eq!(read(&identity_path).unwrap(), source_content);
+        assert!(!source_directory.path().join("change.json").exists());
+        let copied = target
+            .get_all(&Identity::table_name(), &QueryOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(copied[0].get_fields()["name"], "billing");
+
+        let report = StoreMigrationService::migrate(&source, &target)
+            .await
+            .unwrap();
+        let identities = table_report(&report, &Identity::table_name());
+        assert_eq!((identities.copied, identities.already_present), (0, 1));
+        assert!(report.is_consistent());
+    }
+
+    #[tokio::test]
+    async fn rejects_the_writes_to_the_source() {
+        let directory = tempfile::tempdir().unwrap();
+        let source = FileDatabase::new(directory.path());
+        source.connect_read_only().await.unwrap();
+
+        let result = source
+            .insert(&Identity::table_name(), &identity("billing"))
+            .await;
+        assert!(matches!(result, Err(DatabaseError::Io(_))));
+        assert!(!directory.path().join("identity.json").exists());
+    }
+}
@@ -1,10 +1,16 @@
-use tracing::info;
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 // Libs
 use tracing_subscriber::EnvFilter;
 use ulid::Ulid;
 use url::Url;
 
-use crate::utils::{get_env, get_optional_env, gracefully_shutdown};
+use crate::utils::{
+    check_writable_dir, get_optional_secret, get_optional_setting, get_secret, get_setting,
+    parse_encryption_key, EncryptionKey, Secret,
+};
 
 // Structs
 /**
@@ -18,110 +24,250 @@ impl EnvValidator {
     /**
     Validate and return the log level.
     */
-    pub fn validate_log_level() -> EnvFilter {
-        match get_optional_env("POLIWARDEN_LOG_LEVEL") {
-            Some(_) => EnvFilter::from_env("POLIWARDEN_LOG_LEVEL"),
-            None => EnvFilter::new("INFO"),
+    pub fn validate_log_level() -> Result<EnvFilter, String> {
+        match get_optional_setting("POLIWARDEN_LOG_LEVEL") {
+            Some(log_level) => EnvFilter::try_new(log_level)
+                .map_err(|e| format!("The provided log level is invalid: {e}")),
+            None => Ok(EnvFilter::new("INFO")),
         }
     }
 
     /**
     Validate and return the master bearer.
+    It can also be defined in the file of `POLIWARDEN_MASTER_BEARER_FILE`.
+    Without it, the migrations generate a bootstrap bearer.
     */
-    pub fn validate_master_bearer() -> String {
-        match get_optional_env("POLIWARDEN_MASTER_BEARER") {
-            None => {
-                let master_bearer = Ulid::new().to_string();
-                info!(
-                    master_bearer = master_bearer.as_str(),
-                    "Generated a new master bearer.",
-                );
-                master_bearer
-            }
+    pub fn validate_master_bearer() -> Result<Option<Secret<String>>, String> {
+        match get_optional_secret("POLIWARDEN_MASTER_BEARER")? {
+            None => Ok(None),
             Some(master_bearer) => match Ulid::from_string(&master_bearer) {
-                Ok(_) => master_bearer,
-                Err(_e) => gracefully_shutdown("The provided master bearer is invalid."),
+                Ok(_) => Ok(Some(Secret::new(master_bearer))),
+                Err(_e) => Err(String::from("The provided master bearer is invalid.")),
             },
         }
     }
 
     /**
     Validate and return the master certificate.
+    It can also be defined in the file of `POLIWARDEN_MASTER_CERT_FILE`, which suits the multi-line PEM.
     */
-    pub fn validate_master_cert() -> Option<String> {
-        match get_optional_env("POLIWARDEN_MASTER_CERT") {
-            None => None,
-            Some(master_cert) => Some(master_cert),
-        }
+    pub fn validate_master_cert() -> Result<Option<Secret<String>>, String> {
+        Ok(get_optional_secret("POLIWARDEN_MASTER_CERT")?.map(Secret::new))
     }
 
     /**
     Validate and return the bearer header.
     */
-    pub fn validate_bearer_header() -> String {
-        match get_optional_env("POLIWARDEN_BEARER_HEADER") {
-            None => String::from("Poliwarden-Bearer"),
-            Some(bearer_header) => bearer_header,
+    pub fn validate_bearer_header() -> Result<String, String> {
+        match get_optional_setting("POLIWARDEN_BEARER_HEADER") {
+            None => Ok(String::from("Poliwarden-Bearer")),
+            Some(bearer_header) => Ok(bearer_header),
         }
     }
 
     /**
     Validate and return the disable bearer.
     */
-    pub fn validate_disable_bearer() -> bool {
-        match get_optional_env("POLIWARDEN_DISABLE_BEARER") {
-            None => false,
+    pub fn validate_disable_bearer() -> Result<bool, String> {
+        match get_optional_setting("POLIWARDEN_DISABLE_BEARER") {
+            None => Ok(false),
             Some(disable_bearer) => match disable_bearer.to_ascii_lowercase().as_str() {
-                "true" => true,
-                "false" => false,
-                _ => gracefully_shutdown("Invalid disable bearer"),
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(String::from("Invalid disable bearer")),
+            },
+        }
+    }
+
+    /**
+    Validate and return the flag to reject the methods that aren't known HTTP methods.
+    */
+    pub fn validate_reject_unknown_methods() -> Result<bool, String> {
+        match get_optional_setting("POLIWARDEN_REJECT_UNKNOWN_METHODS") {
+            None => Ok(false),
+            Some(reject) => match reject.to_ascii_lowercase().as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(String::from("Invalid reject unknown methods")),
             },
         }
     }
 
-    // db_url: get_env("POLIWARDEN_DB_URL"),
-    // db_port: get_env("POLIWARDEN_DB_PORT"),
-    // db_username: get_env("POLIWARDEN_DB_USERNAME"),
-    // db_password: get_env("POLIWARDEN_DB_PASSWORD"),
-    // db_name: get_env("POLIWARDEN_DB_NAME"),
+    /**
+    Validate and return the FileDB directory.
+    When defined, it must be an existing and writable directory.
+    */
+    pub fn validate_filedb_path() -> Result<PathBuf, String> {
+        let filedb_path = match get_optional_setting("POLIWARDEN_FILEDB_PATH") {
+            None => return Ok(PathBuf::from("./vol/")),
+            Some(filedb_path) => PathBuf::from(filedb_path),
+        };
+
+        if !filedb_path.is_dir() {
+            return Err(String::from(
+                "POLIWARDEN_FILEDB_PATH must be an existing directory.",
+            ));
+        }
+        if let Err(e) = check_writable_dir(&filedb_path) {
+            return Err(format!("POLIWARDEN_FILEDB_PATH must be writable: {e}"));
+        }
+
+        Ok(filedb_path)
+    }
+
+    /**
+    Validate and return the path of the policy file.
+    The file must exist and be a YAML or TOML file.
+    */
+    pub fn validate_policy_file() -> Result<Option<PathBuf>, String> {
+        let policy_file = match get_optional_setting("POLIWARDEN_POLICY_FILE") {
+            None => return Ok(None),
+            Some(policy_file) => PathBuf::from(policy_file),
+        };
+
+        if !policy_file.is_file() {
+            return Err(String::from(
+                "POLIWARDEN_POLICY_FILE must be an existing file.",
+            ));
+        }
+        match policy_file
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("yaml" | "yml" | "toml") => Ok(Some(policy_file)),
+            _ => Err(String::from(
+                "POLIWARDEN_POLICY_FILE must be a YAML or TOML file.",
+            )),
+        }
+    }
+
+    /**
+    Validate and return the path of the audit file.
+    Its directory must exist and be writable, the file is created on the first record.
+    */
+    pub fn validate_audit_file() -> Result<Option<PathBuf>, String> {
+        let audit_file = match get_optional_setting("POLIWARDEN_AUDIT_FILE") {
+            None => return Ok(None),
+            Some(audit_file) => PathBuf::from(audit_file),
+        };
+
+        if audit_file.is_dir() {
+            return Err(String::from("POLIWARDEN_AUDIT_FILE must be a file."));
+        }
+        let directory = match audit_file.parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory,
+            _ => Path::new("."),
+        };
+        if let Err(e) = check_writable_dir(directory) {
+            return Err(format!(
+                "The directory of POLIWARDEN_AUDIT_FILE must be writable: {e}"
+            ));
+        }
+
+        Ok(Some(audit_file))
+    }
+
+    /**
+    Validate and return a key, as the FileDB and audit keys.
+    The key can be defined as hex in the variable or in the file of the `{env_name}_FILE` variable.
+    */
+    pub fn validate_key(env_name: &str) -> Result<Option<EncryptionKey>, String> {
+        let Some(hex_key) = get_optional_secret(env_name)? else {
+            return Ok(None);
+        };
+
+        match parse_encryption_key(&hex_key) {
+            Ok(key) => Ok(Some(key)),
+            Err(e) => Err(format!("Invalid {env_name}: {e}")),
+        }
+    }
+
     /**
     Validate and return the database URL.
     */
-    pub fn validate_db_url() -> String {
-        match Url::parse(&get_env("POLIWARDEN_DB_URL")) {
-            Ok(url) => url.to_string(),
-            Err(_) => gracefully_shutdown("Invalid database URL."),
+    pub fn validate_db_url() -> Result<String, String> {
+        match Url::parse(&get_setting("POLIWARDEN_DB_URL")?) {
+            Ok(url) => Ok(url.to_string()),
+            Err(_) => Err(String::from("Invalid database URL.")),
         }
     }
 
     /**
     Validate and return the database port.
     */
-    pub fn validate_db_port() -> String {
-        match get_env("POLIWARDEN_DB_PORT").parse::<u16>() {
-            Ok(_) => get_env("POLIWARDEN_DB_PORT"),
-            Err(_) => gracefully_shutdown("Invalid database port."),
+    pub fn validate_db_port() -> Result<String, String> {
+        let db_port = get_setting("POLIWARDEN_DB_PORT")?;
+        match db_port.parse::<u16>() {
+            Ok(_) => Ok(db_port),
+            Err(_) => Err(String::from("Invalid database port.")),
         }
     }
 
     /**
     Validate and return the database username.
     */
-    pub fn validate_db_username() -> String {
-        get_env("POLIWARDEN_DB_USERNAME")
+    pub fn validate_db_username() -> Result<String, String> {
+        get_setting("POLIWARDEN_DB_USERNAME")
     }
 
     /**
     Validate and return the database password.
+    It can also be defined in the file of `POLIWARDEN_DB_PASSWORD_FILE`.
     */
-    pub fn validate_db_password() -> String {
-        get_env("POLIWARDEN_DB_PASSWORD")
+    pub fn validate_db_password() -> Result<Secret<String>, String> {
+        get_secret("POLIWARDEN_DB_PASSWORD").map(Secret::new)
     }
 
     /**
     Validate and return the database name.
     */
-    pub fn validate_db_name() -> String {
-        get_env("POLIWARDEN_DB_NAME")
+    pub fn validate_db_name() -> Result<String, String> {
+        get_setting("POLIWARDEN_DB_NAME")
+    }
+
+    /**
+    Validate and return the address of the admin server.
+    */
+    pub fn validate_admin_address() -> Result<SocketAddr, String> {
+        match get_optional_setting("POLIWARDEN_ADMIN_ADDRESS") {
+            None => Ok(SocketAddr::from(([0, 0, 0, 0], 8081))),
+            Some(address) => address
+                .parse()
+                .map_err(|_| String::from("Invalid POLIWARDEN_ADMIN_ADDRESS.")),
+        }
+    }
+
+    /**
+    Validate and return the base URL of the OTLP/HTTP collector, such as `http://localhost:4318`.
+    */
+    pub fn validate_otlp_endpoint() -> Result<Option<Url>, String> {
+        match get_optional_setting("POLIWARDEN_OTLP_ENDPOINT") {
+            None => Ok(None),
+            Some(endpoint) => match Url::parse(&endpoint) {
+                Ok(url) if ["http", "https"].contains(&url.scheme()) => Ok(Some(url)),
+                _ => Err(String::from("Invalid POLIWARDEN_OTLP_ENDPOINT.")),
+            },
+        }
+    }
+
+    /**
+    Validate and return a port of the protocol features.
+    */
+    pub fn validate_feature_port(name: &str, default: u16) -> Result<u16, String> {
+        match get_optional_setting(name) {
+            None => Ok(default),
+            Some(port) => port.parse().map_err(|_| format!("Invalid {name}.")),
+        }
+    }
+
+    /**
+    Validate and return a file of the HTTPS feature, like its certificate or key.
+    */
+    pub fn validate_feature_https_file(name: &str) -> Result<PathBuf, String> {
+        let path = PathBuf::from(get_setting(name)?);
+        match path.is_file() {
+            true => Ok(path),
+            false => Err(format!("{name} must be an existing file.")),
+        }
     }
 }
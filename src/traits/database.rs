@@ -1,5 +1,8 @@
 // Libs
-use crate::{errors::DBResult, schemas::Model};
+use crate::{
+    errors::DBResult,
+    schemas::{Model, QueryOptions},
+};
 use async_trait::async_trait;
 
 // Traits
@@ -16,15 +19,26 @@ pub trait Database: Send + Sync {
     */
     async fn connect(&self) -> DBResult<()>;
 
+    /**
+    Connect to the database only to read it, without creating, migrating or rewriting anything.
+    The missing tables are read as empty.
+    */
+    async fn connect_read_only(&self) -> DBResult<()>;
+
+    /**
+    Check the database is reachable, without changing it. It's cheap enough for the readiness probes.
+    */
+    async fn ping(&self) -> DBResult<()>;
+
     /**
     Get a single record from the database.
     */
     async fn get(&self, table_name: &str, id: &str) -> DBResult<Option<Model>>;
 
     /**
-    Get all records from the database.
+    Get the records from the database, filtered, sorted and paginated by the options.
     */
-    async fn get_all(&self, table_name: &str) -> DBResult<Vec<Model>>;
+    async fn get_all(&self, table_name: &str, options: &QueryOptions) -> DBResult<Vec<Model>>;
 
     /**
     Insert a record into the database.
@@ -32,9 +46,25 @@ pub trait Database: Send + Sync {
     async fn insert(&self, table_name: &str, data: &Model) -> DBResult<()>;
 
     /**
-    Update a record in the database.
+    Insert the records into the database, in a single write if the backend supports it.
     */
-    async fn update(&self, table_name: &str, data: &Model) -> DBResult<Option<()>>;
+    async fn insert_many(&self, table_name: &str, data: &[Model]) -> DBResult<()> {
+        for model in data {
+            self.insert(table_name, model).await?;
+        }
+        Ok(())
+    }
+
+    /**
+    Update a record in the database, incrementing its revision. Returns the updated record.
+    If the expected revision differs from the stored one, a `DatabaseError::StaleRevision` is returned.
+    */
+    async fn update(
+        &self,
+        table_name: &str,
+        data: &Model,
+        expected_revision: Option<u64>,
+    ) -> DBResult<Option<Model>>;
 
     /**
     Delete a record from the database.
@@ -42,9 +72,44 @@ pub trait Database: Send + Sync {
     async fn delete(&self, table_name: &str, id: &str) -> DBResult<Option<()>>;
 
     /**
-    Run a query the database.
+    Create a table in the database. It does nothing if the table already exists.
+    */
+    async fn create_table(&self, table_name: &str) -> DBResult<()>;
+
+    /**
+    Get the records whose field is equal to the value.
+    */
+    async fn find_by(&self, table_name: &str, field: &str, value: &str) -> DBResult<Vec<Model>> {
+        let options = QueryOptions::new().filter(field, value);
+        self.get_all(table_name, &options).await
+    }
+
+    /**
+    Count the records matching the options' filters.
+    */
+    async fn count(&self, table_name: &str, options: &QueryOptions) -> DBResult<usize> {
+        let options = QueryOptions {
+            filters: options.filters.clone(),
+            ..Default::default()
+        };
+        Ok(self.get_all(table_name, &options).await?.len())
+    }
+
+    /**
+    Begin a transaction. The writes after it are only kept if `commit()` is called.
+    Only one transaction can be active at a time.
+    */
+    async fn begin(&self) -> DBResult<()>;
+
+    /**
+    Commit the active transaction.
+    */
+    async fn commit(&self) -> DBResult<()>;
+
+    /**
+    Rollback the writes of the active transaction.
     */
-    async fn query(&self, query: &str) -> DBResult<Vec<Model>>;
+    async fn rollback(&self) -> DBResult<()>;
 
     /**
     Check if is the first time the database is being used.
@@ -1,7 +1,7 @@
 // Libs
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
-
-use crate::schemas::{Identity, Policy};
+use crate::{errors::DatabaseError, schemas::Model};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
 // Traits
 pub trait ModelProperties: Send + Sync {
@@ -11,14 +11,42 @@ pub trait ModelProperties: Send + Sync {
     fn get_table_name(&self) -> String;
 
     /**
-    Get the respective SQL migration script for the model, based on the database.
+    Get the model's id.
     */
-    fn get_migration_script(&self, database_name: &str) -> String;
+    fn get_id(&self) -> String;
 
     /**
-    Get the model's id.
+    Get the model's revision, incremented on every update.
     */
-    fn get_id(&self) -> String;
+    fn get_revision(&self) -> u64;
+
+    fn set_revision(&mut self, revision: u64);
 }
 
-pub trait SerdeModel: DeserializeOwned + Send + Serialize + Sync {}
+/**
+An entity that can be stored as a `Model` and accessed through a `Repository`.
+*/
+pub trait SerdeModel:
+    Clone
+    + DeserializeOwned
+    + Into<Model>
+    + Send
+    + Serialize
+    + Sync
+    + TryFrom<Model, Error = DatabaseError>
+{
+    /**
+    Get the table name for the entity, without an instance.
+    */
+    fn table_name() -> String;
+}
+
+/**
+An entity whose management changes are recorded, with its snapshots.
+*/
+pub trait Tracked: ModelProperties + SerdeModel {
+    /**
+    Get the snapshot of the entity. It must not contain any secret.
+    */
+    fn snapshot(&self) -> serde_json::Result<Value>;
+}
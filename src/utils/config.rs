@@ -0,0 +1,307 @@
+// Libs
+use super::get_optional_env;
+use arc_swap::ArcSwap;
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, fs::read_to_string};
+use toml::{Table, Value};
+
+// Data
+const SETTING_PREFIX: &str = "POLIWARDEN_";
+
+/**
+The settings that can be defined in the configuration file, without the `POLIWARDEN_` prefix.
+*/
+const CONFIG_KEYS: &[&str] = &[
+    "log_level",
+    "admin_address",
+    "otlp_endpoint",
+    "master_bearer",
+    "master_bearer_file",
+    "master_cert",
+    "master_cert_file",
+    "bearer_header",
+    "disable_bearer",
+    "reject_unknown_methods",
+    "policy_file",
+    "audit_file",
+    "audit_key",
+    "audit_key_file",
+    "filedb_path",
+    "filedb_key",
+    "filedb_key_file",
+    "filedb_previous_key",
+    "filedb_previous_key_file",
+    "db_url",
+    "db_port",
+    "db_username",
+    "db_password",
+    "db_password_file",
+    "db_name",
+    "feature_http_port",
+    "feature_https_port",
+    "feature_https_cert",
+    "feature_https_key",
+];
+
+/**
+The loaded configuration file. It's swapped when the settings are reloaded.
+*/
+pub static CONFIG_FILE: Lazy<ArcSwap<ConfigFile>> =
+    Lazy::new(|| ArcSwap::from_pointee(ConfigFile::load().0));
+
+// Structs
+/**
+The flattened values of the TOML file defined by `POLIWARDEN_CONFIG_FILE`.
+The nested tables are joined with `_`, so `[db] url` is the same as `db_url`.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct ConfigFile {
+    values: HashMap<String, String>,
+}
+
+// Implementations
+impl ConfigFile {
+    /**
+    Load the configuration file. Without `POLIWARDEN_CONFIG_FILE`, the configuration is empty.
+    The valid keys are kept, so they can be validated with the errors of the invalid ones.
+    */
+    pub fn load() -> (Self, Vec<String>) {
+        let Some(path) = get_optional_env("POLIWARDEN_CONFIG_FILE") else {
+            return (Self::default(), Vec::new());
+        };
+
+        match read_to_string(&path) {
+            Ok(content) => Self::parse(&content),
+            Err(e) => (
+                Self::default(),
+                vec![format!("Couldn't read POLIWARDEN_CONFIG_FILE: {e}")],
+            ),
+        }
+    }
+
+    /**
+    Parse the content of a configuration file, with the errors of its invalid keys.
+    */
+    fn parse(content: &str) -> (Self, Vec<String>) {
+        let mut config = Self::default();
+        let table = match content.parse::<Table>() {
+            Ok(table) => table,
+            Err(e) => return (config, vec![format!("Invalid POLIWARDEN_CONFIG_FILE: {e}")]),
+        };
+
+        let mut errors = Vec::new();
+        config.flatten(None, table, &mut errors);
+        (config, errors)
+    }
+
+    /**
+    Get the value of a setting, by its environment variable name.
+    */
+    pub fn get(&self, name: &str) -> Option<String> {
+        let key = name.strip_prefix(SETTING_PREFIX)?.to_ascii_lowercase();
+        self.values
+            .get(&key)
+            .filter(|value| !value.is_empty())
+            .cloned()
+    }
+
+    /**
+    Get a setting from the environment lookup or, if it's not set, from the file.
+    */
+    fn get_layered(&self, name: &str, get_env: impl Fn(&str) -> Option<String>) -> Option<String> {
+        get_env(name).or_else(|| self.get(name))
+    }
+
+    fn flatten(&mut self, prefix: Option<&str>, table: Table, errors: &mut Vec<String>) {
+        for (key, value) in table {
+            let key = match prefix {
+                None => key,
+                Some(prefix) => format!("{prefix}_{key}"),
+            };
+
+            let value = match value {
+                Value::Table(table) => {
+                    self.flatten(Some(&key), table, errors);
+                    continue;
+                }
+                Value::Array(_) => {
+                    errors.push(format!("The configuration {key} must be a single value."));
+                    continue;
+                }
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+
+            match CONFIG_KEYS.contains(&key.as_str()) {
+                true => {
+                    self.values.insert(key, value);
+                }
+                false => errors.push(format!("The configuration {key} is an unknown setting.")),
+            }
+        }
+    }
+}
+
+// Functions
+/**
+Get a setting from the environment or, if it's not set, from the configuration file.
+*/
+pub fn get_optional_setting(name: &str) -> Option<String> {
+    CONFIG_FILE.load().get_layered(name, get_optional_env)
+}
+
+/**
+Get a required setting from the environment or the configuration file.
+*/
+pub fn get_setting(name: &str) -> Result<String, String> {
+    get_optional_setting(name).ok_or_else(|| format!("{name} must be set."))
+}
+
+/**
+Get a secret setting, defined directly or in the file of the `{name}_FILE` setting.
+The file's trailing line break is removed, so mounted secrets can be used as they are.
+*/
+pub fn get_optional_secret(name: &str) -> Result<Option<String>, String> {
+    read_secret(name, get_optional_setting)
+}
+
+/**
+Get a secret setting with the lookup, defined directly or in the file of the `{name}_FILE` setting.
+*/
+fn read_secret(
+    name: &str,
+    get_setting: impl Fn(&str) -> Option<String>,
+) -> Result<Option<String>, String> {
+    let file_name = format!("{name}_FILE");
+    match (get_setting(name), get_setting(&file_name)) {
+        (None, None) => Ok(None),
+        (Some(secret), None) => Ok(Some(secret)),
+        (None, Some(path)) => match read_to_string(&path) {
+            Ok(secret) => Ok(Some(secret.trim_end_matches(['\r', '\n']).to_string())),
+            Err(e) => Err(format!("Couldn't read {file_name}: {e}")),
+        },
+        (Some(_), Some(_)) => Err(format!("Only one of {name} and {file_name} can be set.")),
+    }
+}
+
+/**
+Get a required secret setting, defined directly or in the file of the `{name}_FILE` setting.
+*/
+pub fn get_secret(name: &str) -> Result<String, String> {
+    get_optional_secret(name)?.ok_or_else(|| format!("{name} or {name}_FILE must be set."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const CONTENT: &str = r#"
+log_level = "debug"
+disable_bearer = true
+
+[db]
+url = "db.local"
+port = 8000
+"#;
+
+    fn env(variables: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let variables: HashMap<String, String> = variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| variables.get(name).cloned()
+    }
+
+    fn secret_file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn flattens_the_nested_tables() {
+        let (config, errors) = ConfigFile::parse(CONTENT);
+
+        assert!(errors.is_empty());
+        assert_eq!(config.get("POLIWARDEN_LOG_LEVEL").unwrap(), "debug");
+        assert_eq!(config.get("POLIWARDEN_DISABLE_BEARER").unwrap(), "true");
+        assert_eq!(config.get("POLIWARDEN_DB_URL").unwrap(), "db.local");
+        assert_eq!(config.get("POLIWARDEN_DB_PORT").unwrap(), "8000");
+        assert_eq!(config.get("LOG_LEVEL"), None);
+    }
+
+    #[test]
+    fn rejects_the_unknown_and_multiple_values() {
+        let (config, errors) =
+            ConfigFile::parse("log_level = \"info\"\nunknown = 1\ndb_name = [\"a\"]");
+
+        assert_eq!(
+            errors,
+            [
+                "The configuration db_name must be a single value.",
+                "The configuration unknown is an unknown setting.",
+            ]
+        );
+        assert_eq!(config.get("POLIWARDEN_LOG_LEVEL").unwrap(), "info");
+        assert!(
+            ConfigFile::parse("log_level = ").1[0].starts_with("Invalid POLIWARDEN_CONFIG_FILE")
+        );
+    }
+
+    #[test]
+    fn prefers_the_environment_to_the_file() {
+        let (config, _) = ConfigFile::parse(CONTENT);
+        let get_env = env(&[("POLIWARDEN_LOG_LEVEL", "warn")]);
+
+        let log_level = config.get_layered("POLIWARDEN_LOG_LEVEL", &get_env);
+        assert_eq!(log_level.unwrap(), "warn");
+        let db_url = config.get_layered("POLIWARDEN_DB_URL", &get_env);
+        assert_eq!(db_url.unwrap(), "db.local");
+        assert_eq!(config.get_layered("POLIWARDEN_DB_NAME", &get_env), None);
+    }
+
+    #[test]
+    fn reads_the_secret_from_its_file() {
+        let file = secret_file("secret\r\n");
+        let path = file.path().to_str().unwrap();
+        let (config, _) = ConfigFile::parse(&format!("master_bearer_file = {path:?}"));
+
+        let get_setting = |name: &str| config.get_layered(name, env(&[]));
+        let secret = read_secret("POLIWARDEN_MASTER_BEARER", get_setting).unwrap();
+        assert_eq!(secret.unwrap(), "secret");
+
+        let get_setting = env(&[("POLIWARDEN_MASTER_BEARER", "direct secret\n")]);
+        let secret = read_secret("POLIWARDEN_MASTER_BEARER", get_setting).unwrap();
+        assert_eq!(secret.unwrap(), "direct secret\n");
+
+        assert_eq!(read_secret("POLIWARDEN_MASTER_BEARER", env(&[])), Ok(None));
+    }
+
+    #[test]
+    fn rejects_the_secret_and_its_file_together() {
+        let file = secret_file("file secret");
+        let path = file.path().to_str().unwrap();
+        // The secret is in the environment and its file in the configuration file.
+        let (config, _) = ConfigFile::parse(&format!("master_bearer_file = {path:?}"));
+        let get_env = env(&[("POLIWARDEN_MASTER_BEARER", "secret")]);
+
+        let get_setting = |name: &str| config.get_layered(name, &get_env);
+        assert_eq!(
+            read_secret("POLIWARDEN_MASTER_BEARER", get_setting),
+            Err(String::from(
+                "Only one of POLIWARDEN_MASTER_BEARER and POLIWARDEN_MASTER_BEARER_FILE can be set."
+            ))
+        );
+    }
+
+    #[test]
+    fn fails_on_a_missing_secret_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("missing");
+        let get_setting = env(&[("POLIWARDEN_AUDIT_KEY_FILE", path.to_str().unwrap())]);
+
+        let error = read_secret("POLIWARDEN_AUDIT_KEY", get_setting).unwrap_err();
+        assert!(error.starts_with("Couldn't read POLIWARDEN_AUDIT_KEY_FILE: "));
+    }
+}
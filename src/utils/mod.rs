@@ -1,7 +1,14 @@
+pub use config::*;
+pub use secret::*;
 pub use security::*;
 pub use settings::*;
+pub use telemetry::*;
 pub use utils::*;
 
+pub mod config;
+pub mod secret;
 pub mod security;
 pub mod settings;
+pub mod telemetry;
+#[allow(clippy::module_inception)]
 pub mod utils;
@@ -0,0 +1,43 @@
+// Libs
+use serde::{Deserialize, Deserializer};
+use std::fmt::{self, Debug, Formatter};
+
+// Structs
+/**
+A credential that's redacted when formatted, so it never reaches the logs.
+Its value is only read through `expose()`. It can't be serialized.
+*/
+#[derive(Clone, Default, PartialEq)]
+pub struct Secret<T>(T);
+
+// Implementations
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /**
+    Get the value of the secret. It must not be logged.
+    */
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
@@ -3,6 +3,22 @@ use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2,
 };
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+// Data
+/**
+The size of the keys used by the AEAD functions.
+*/
+pub const ENCRYPTION_KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 24;
+
+// Types
+pub type EncryptionKey = [u8; ENCRYPTION_KEY_SIZE];
 
 // Functions
 /**
@@ -16,11 +32,104 @@ pub fn generate_salt() -> String {
 Hashes some content using the Argon2 algorithm.
 To get a salt, call `generate_salt()`.
 */
-pub fn hash_argon2(content: &str, salt: &str) -> String {
+pub fn hash_argon2(content: &str, salt: &str) -> Result<String, argon2::Error> {
     let argon = Argon2::default();
     let mut hash = vec![0u8; 32];
-    argon
-        .hash_password_into(content.as_bytes(), salt.as_bytes(), &mut hash)
-        .unwrap();
-    hex::encode(hash)
+    argon.hash_password_into(content.as_bytes(), salt.as_bytes(), &mut hash)?;
+    Ok(hex::encode(hash))
+}
+
+/**
+Hashes some content using SHA-256, returning it hex encoded.
+It's fast and unsalted, so it must never be used for credentials.
+*/
+pub fn hash_sha256(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+/**
+Authenticates some content using HMAC-SHA256, returning it hex encoded.
+*/
+pub fn hmac_sha256(key: &EncryptionKey, content: &[u8]) -> String {
+    let mut mac = new_hmac(key);
+    mac.update(content);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/**
+Checks a hex encoded MAC from `hmac_sha256()`, in constant time.
+*/
+pub fn verify_hmac_sha256(key: &EncryptionKey, content: &[u8], hex_mac: &str) -> bool {
+    let Ok(expected) = hex::decode(hex_mac) else {
+        return false;
+    };
+    let mut mac = new_hmac(key);
+    mac.update(content);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn new_hmac(key: &EncryptionKey) -> Hmac<Sha256> {
+    <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any size.")
+}
+
+/**
+Parses a hex encoded encryption key.
+*/
+pub fn parse_encryption_key(hex_key: &str) -> Result<EncryptionKey, String> {
+    let key = hex::decode(hex_key.trim()).map_err(|e| e.to_string())?;
+    key.try_into().map_err(|_| {
+        format!(
+            "The key must have {} bytes ({} hex characters).",
+            ENCRYPTION_KEY_SIZE,
+            ENCRYPTION_KEY_SIZE * 2
+        )
+    })
+}
+
+/**
+Encrypts some content using XChaCha20-Poly1305.
+The `aad` isn't encrypted, but it must be the same to decrypt the content.
+Returns the random nonce followed by the ciphertext.
+*/
+pub fn encrypt_aead(
+    key: &EncryptionKey,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, chacha20poly1305::Error> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(
+        &nonce,
+        Payload {
+            msg: plaintext,
+            aad,
+        },
+    )?;
+
+    let mut result = nonce.to_vec();
+    result.extend(ciphertext);
+    Ok(result)
+}
+
+/**
+Decrypts some content encrypted by `encrypt_aead()`.
+*/
+pub fn decrypt_aead(
+    key: &EncryptionKey,
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, chacha20poly1305::Error> {
+    if data.len() < NONCE_SIZE {
+        return Err(chacha20poly1305::Error);
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+    let cipher = XChaCha20Poly1305::new(key.into());
+    cipher.decrypt(
+        XNonce::from_slice(nonce),
+        Payload {
+            msg: ciphertext,
+            aad,
+        },
+    )
 }
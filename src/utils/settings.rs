@@ -1,64 +1,273 @@
 // Libs
+use super::{gracefully_shutdown, ConfigFile, EncryptionKey, Secret, CONFIG_FILE};
 use crate::services::EnvValidator;
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
-use std::sync::Arc;
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+};
+use tracing::error;
 
 // Data
-pub static DEFAULT_SETTINGS: Lazy<Arc<DefaultSettings>> = Lazy::new(|| {
-    Arc::new(DefaultSettings {
-        master_bearer: EnvValidator::validate_master_bearer(),
-        master_cert: EnvValidator::validate_master_cert(),
-        bearer_header: EnvValidator::validate_bearer_header(),
-        disable_bearer: EnvValidator::validate_disable_bearer(),
-    })
+/**
+All the settings, loaded from the configuration file and the environment.
+They're validated together, so every error is reported at once.
+*/
+static SETTINGS: Lazy<Settings> = Lazy::new(|| match Settings::load() {
+    Ok(settings) => settings,
+    Err(errors) => {
+        for e in &errors {
+            error!(error = e.as_str(), "Invalid setting.");
+        }
+        gracefully_shutdown(format!("Found {} invalid settings.", errors.len()))
+    }
 });
 
-pub static DATABASE_SETTINGS: Lazy<Arc<DatabaseSettings>> = Lazy::new(|| {
-    Arc::new(DatabaseSettings {
-        db_url: EnvValidator::validate_db_url(),
-        db_port: EnvValidator::validate_db_port(),
-        db_username: EnvValidator::validate_db_username(),
-        db_password: EnvValidator::validate_db_password(),
-        db_name: EnvValidator::validate_db_name(),
-    })
-});
+/**
+The default settings. They're swapped atomically on reload, so readers keep a consistent snapshot.
+*/
+pub static DEFAULT_SETTINGS: Lazy<ArcSwap<DefaultSettings>> =
+    Lazy::new(|| ArcSwap::from_pointee(SETTINGS.default.clone()));
+
+// Read by the SurrealDB backend, which isn't built yet. The settings are already validated.
+#[allow(dead_code)]
+pub static DATABASE_SETTINGS: Lazy<Arc<DatabaseSettings>> = Lazy::new(|| SETTINGS.database.clone());
+
+pub static FILEDB_SETTINGS: Lazy<Arc<FileDatabaseSettings>> = Lazy::new(|| SETTINGS.filedb.clone());
 
-// pub const HTTP_SETTINGS: Lazy<Arc<HttpSettings>> = Lazy::new(|| {
-//     Arc::new(HttpSettings {
-//         feature_http_port: get_env("POLIWARDEN_FEATURE_HTTP_PORT"),
-//     })
-// });
+pub static ADMIN_SETTINGS: Lazy<Arc<AdminSettings>> = Lazy::new(|| SETTINGS.admin.clone());
 
-// pub const HTTPS_SETTINGS: Lazy<Arc<HttpsSettings>> = Lazy::new(|| {
-//     Arc::new(HttpsSettings {
-//         feature_https_port: get_env("POLIWARDEN_FEATURE_HTTPS_PORT"),
-//         feature_https_cert: get_env("POLIWARDEN_FEATURE_HTTPS_CERT"),
-//         feature_https_key: get_env("POLIWARDEN_FEATURE_HTTPS_KEY"),
-//     })
-// });
+pub static AUDIT_SETTINGS: Lazy<Arc<AuditSettings>> = Lazy::new(|| SETTINGS.audit.clone());
+
+pub static HTTP_SETTINGS: Lazy<Arc<HttpSettings>> = Lazy::new(|| SETTINGS.http.clone());
+
+// Read by the HTTPS server, which isn't built yet. The settings are already validated.
+#[allow(dead_code)]
+pub static HTTPS_SETTINGS: Lazy<Arc<HttpsSettings>> = Lazy::new(|| SETTINGS.https.clone());
 
 // Structs
+#[derive(Clone)]
 pub struct DefaultSettings {
-    pub master_bearer: String,
-    pub master_cert: Option<String>,
+    pub master_bearer: Option<Secret<String>>,
+    pub master_cert: Option<Secret<String>>,
     pub bearer_header: String,
     pub disable_bearer: bool,
+    pub reject_unknown_methods: bool,
+    pub policy_file: Option<PathBuf>,
 }
 
+// Like `DATABASE_SETTINGS`, the fields are only read by the SurrealDB backend.
+#[allow(dead_code)]
+#[derive(Default)]
 pub struct DatabaseSettings {
     pub db_url: String,
     pub db_port: String,
     pub db_username: String,
-    pub db_password: String,
+    pub db_password: Secret<String>,
     pub db_name: String,
 }
 
-// pub struct HttpSettings {
-//     feature_http_port: String,
-// }
+pub struct FileDatabaseSettings {
+    pub filedb_path: PathBuf,
+    pub filedb_key: Option<EncryptionKey>,
+    pub filedb_previous_key: Option<EncryptionKey>,
+}
+
+pub struct AdminSettings {
+    pub admin_address: SocketAddr,
+}
+
+pub struct AuditSettings {
+    /**
+    The JSON lines file of the audit records, `audit.jsonl` in the FileDB directory by default.
+    */
+    pub audit_file: PathBuf,
+    /**
+    The key of the audit head's HMAC. Without it, the head isn't signed.
+    */
+    pub audit_key: Option<EncryptionKey>,
+}
+
+#[derive(Default)]
+pub struct HttpSettings {
+    pub feature_http_port: u16,
+}
+
+// Like `HTTPS_SETTINGS`, the fields are only read by the HTTPS server.
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct HttpsSettings {
+    pub feature_https_port: u16,
+    pub feature_https_cert: PathBuf,
+    pub feature_https_key: PathBuf,
+}
+
+pub struct Settings {
+    pub default: DefaultSettings,
+    // The database and HTTPS settings are only read through their statics.
+    #[allow(dead_code)]
+    pub database: Arc<DatabaseSettings>,
+    pub filedb: Arc<FileDatabaseSettings>,
+    pub admin: Arc<AdminSettings>,
+    pub audit: Arc<AuditSettings>,
+    pub http: Arc<HttpSettings>,
+    #[allow(dead_code)]
+    pub https: Arc<HttpsSettings>,
+}
 
-// pub struct HttpsSettings {
-//     feature_https_port: String,
-//     feature_https_cert: String,
-//     feature_https_key: String,
-// }
+// Implementations
+impl Settings {
+    /**
+    Load and validate all the settings, shutting down with every error found.
+    */
+    pub fn init() {
+        Lazy::force(&SETTINGS);
+    }
+
+    /**
+    Load the configuration file, then validate the settings with the environment overriding it.
+    The database and HTTPS settings are only required when their features are enabled.
+    */
+    fn load() -> Result<Self, Vec<String>> {
+        let (config_file, mut errors) = ConfigFile::load();
+        CONFIG_FILE.store(Arc::new(config_file));
+
+        // The log level and telemetry are applied by the logger, they're only validated here.
+        if let Err(e) = EnvValidator::validate_log_level() {
+            errors.push(e);
+        }
+        if cfg!(feature = "otel") {
+            if let Err(e) = EnvValidator::validate_otlp_endpoint() {
+                errors.push(e);
+            }
+        }
+
+        let default = DefaultSettings {
+            master_bearer: collect(&mut errors, EnvValidator::validate_master_bearer()),
+            master_cert: collect(&mut errors, EnvValidator::validate_master_cert()),
+            bearer_header: collect(&mut errors, EnvValidator::validate_bearer_header()),
+            disable_bearer: collect(&mut errors, EnvValidator::validate_disable_bearer()),
+            reject_unknown_methods: collect(
+                &mut errors,
+                EnvValidator::validate_reject_unknown_methods(),
+            ),
+            policy_file: collect(&mut errors, EnvValidator::validate_policy_file()),
+        };
+
+        let database = match cfg!(feature = "surreal") {
+            false => DatabaseSettings::default(),
+            true => DatabaseSettings {
+                db_url: collect(&mut errors, EnvValidator::validate_db_url()),
+                db_port: collect(&mut errors, EnvValidator::validate_db_port()),
+                db_username: collect(&mut errors, EnvValidator::validate_db_username()),
+                db_password: collect(&mut errors, EnvValidator::validate_db_password()),
+                db_name: collect(&mut errors, EnvValidator::validate_db_name()),
+            },
+        };
+
+        let filedb = FileDatabaseSettings {
+            filedb_path: collect(&mut errors, EnvValidator::validate_filedb_path()),
+            filedb_key: collect(
+                &mut errors,
+                EnvValidator::validate_key("POLIWARDEN_FILEDB_KEY"),
+            ),
+            filedb_previous_key: collect(
+                &mut errors,
+                EnvValidator::validate_key("POLIWARDEN_FILEDB_PREVIOUS_KEY"),
+            ),
+        };
+
+        let admin = AdminSettings {
+            // The address isn't used when there are errors, so any placeholder works.
+            admin_address: EnvValidator::validate_admin_address().unwrap_or_else(|e| {
+                errors.push(e);
+                SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))
+            }),
+        };
+
+        let audit = AuditSettings {
+            audit_file: collect(&mut errors, EnvValidator::validate_audit_file())
+                .unwrap_or_else(|| filedb.filedb_path.join("audit.jsonl")),
+            audit_key: collect(
+                &mut errors,
+                EnvValidator::validate_key("POLIWARDEN_AUDIT_KEY"),
+            ),
+        };
+
+        let http = HttpSettings {
+            feature_http_port: collect(
+                &mut errors,
+                EnvValidator::validate_feature_port("POLIWARDEN_FEATURE_HTTP_PORT", 80),
+            ),
+        };
+
+        let https = match cfg!(feature = "https") {
+            false => HttpsSettings::default(),
+            true => HttpsSettings {
+                feature_https_port: collect(
+                    &mut errors,
+                    EnvValidator::validate_feature_port("POLIWARDEN_FEATURE_HTTPS_PORT", 443),
+                ),
+                feature_https_cert: collect(
+                    &mut errors,
+                    EnvValidator::validate_feature_https_file("POLIWARDEN_FEATURE_HTTPS_CERT"),
+                ),
+                feature_https_key: collect(
+                    &mut errors,
+                    EnvValidator::validate_feature_https_file("POLIWARDEN_FEATURE_HTTPS_KEY"),
+                ),
+            },
+        };
+
+        match errors.is_empty() {
+            false => Err(errors),
+            true => Ok(Self {
+                default,
+                database: Arc::new(database),
+                filedb: Arc::new(filedb),
+                admin: Arc::new(admin),
+                audit: Arc::new(audit),
+                http: Arc::new(http),
+                https: Arc::new(https),
+            }),
+        }
+    }
+}
+
+impl DefaultSettings {
+    /**
+    Validate the reloadable settings again, keeping the master credentials.
+    The master credentials are only used on the first run, so they're never reloaded.
+    */
+    pub fn reload(&self) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+        let settings = Self {
+            bearer_header: collect(&mut errors, EnvValidator::validate_bearer_header()),
+            disable_bearer: collect(&mut errors, EnvValidator::validate_disable_bearer()),
+            reject_unknown_methods: collect(
+                &mut errors,
+                EnvValidator::validate_reject_unknown_methods(),
+            ),
+            policy_file: collect(&mut errors, EnvValidator::validate_policy_file()),
+            ..self.clone()
+        };
+
+        match errors.is_empty() {
+            true => Ok(settings),
+            false => Err(errors),
+        }
+    }
+}
+
+// Functions
+/**
+Unwrap a validated setting, collecting its error instead of failing.
+*/
+fn collect<T: Default>(errors: &mut Vec<String>, result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        errors.push(e);
+        T::default()
+    })
+}
@@ -0,0 +1,147 @@
+// Libs
+use axum::http::HeaderMap;
+use tracing::Span;
+#[cfg(feature = "otel")]
+use {
+    crate::services::EnvValidator,
+    axum::http::{HeaderName, HeaderValue},
+    once_cell::sync::OnceCell,
+    opentelemetry::{
+        global,
+        propagation::{Extractor, Injector},
+        trace::TracerProvider,
+    },
+    opentelemetry_otlp::{SpanExporter, WithExportConfig},
+    opentelemetry_sdk::{
+        propagation::TraceContextPropagator,
+        trace::{SdkTracer, SdkTracerProvider},
+        Resource,
+    },
+    tracing::Subscriber,
+    tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt},
+    tracing_subscriber::registry::LookupSpan,
+};
+
+// Data
+#[cfg(feature = "otel")]
+static TRACER_PROVIDER: OnceCell<SdkTracerProvider> = OnceCell::new();
+
+// Structs
+/**
+Read the trace context from the request's headers.
+*/
+#[cfg(feature = "otel")]
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+/**
+Write the trace context to the request's headers.
+*/
+#[cfg(feature = "otel")]
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+// Implementations
+#[cfg(feature = "otel")]
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+#[cfg(feature = "otel")]
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        let name = HeaderName::from_bytes(key.as_bytes());
+        let value = HeaderValue::try_from(value);
+        if let (Ok(name), Ok(value)) = (name, value) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+// Functions
+/**
+Get the layer exporting the spans to the OTLP/HTTP collector, if `POLIWARDEN_OTLP_ENDPOINT` is defined.
+It also propagates the W3C trace context. An invalid endpoint is reported with the other settings.
+*/
+#[cfg(feature = "otel")]
+pub fn telemetry_layer<S>() -> Result<Option<OpenTelemetryLayer<S, SdkTracer>>, String>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let Ok(Some(endpoint)) = EnvValidator::validate_otlp_endpoint() else {
+        return Ok(None);
+    };
+
+    let traces_endpoint = endpoint.join("v1/traces").map_err(|e| e.to_string())?;
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(traces_endpoint.as_str())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("poliwarden").build())
+        .build();
+
+    let tracer = provider.tracer("poliwarden");
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let _ = TRACER_PROVIDER.set(provider);
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn telemetry_layer() -> Result<Option<tracing_subscriber::layer::Identity>, String> {
+    Ok(None)
+}
+
+/**
+Export the pending spans. It must be called before the application exits.
+*/
+pub fn shutdown_telemetry() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            tracing::warn!(error = %e, "Couldn't export the pending spans.");
+        }
+    }
+}
+
+/**
+Continue the caller's trace in the span, from its W3C `traceparent` and `tracestate` headers.
+It must be called before the span is entered. Without the telemetry, it does nothing.
+*/
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    #[cfg(feature = "otel")]
+    {
+        let context = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(headers))
+        });
+        if let Err(e) = span.set_parent(context) {
+            tracing::debug!(error = %e, "Couldn't continue the caller's trace.");
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, headers);
+}
+
+/**
+Replace the W3C `traceparent` and `tracestate` headers with the current span's, to be sent to the upstream.
+Without the telemetry, the caller's headers are kept.
+*/
+pub fn inject_trace_context(headers: &mut HeaderMap) {
+    #[cfg(feature = "otel")]
+    {
+        let context = Span::current().context();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut HeaderInjector(headers))
+        });
+    }
+
+    #[cfg(not(feature = "otel"))]
+    let _ = headers;
+}
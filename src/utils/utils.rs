@@ -1,14 +1,32 @@
 // Libs
-use super::DEFAULT_SETTINGS;
+use super::{telemetry_layer, ConfigFile, Secret, CONFIG_FILE, DEFAULT_SETTINGS};
 use crate::{
-    schemas::{Identity, IdentityIn, Model, Policy, PolicyIn},
+    errors::{AppError, AppResult, DBResult},
+    schemas::{Identity, IdentityIn},
     services::EnvValidator,
-    traits::ModelProperties,
 };
-use regex::Regex;
-use std::{env::var, fmt::Display, io::IsTerminal, process::exit};
+use once_cell::sync::OnceCell;
+use std::{
+    env::var,
+    fmt::Display,
+    fs::{remove_file, OpenOptions},
+    future::Future,
+    io::IsTerminal,
+    path::Path,
+    process::exit,
+    sync::Arc,
+};
 use tracing::error;
-use ulid::DecodeError;
+use tracing_subscriber::{
+    fmt::{self, writer::BoxMakeWriter},
+    layer::SubscriberExt,
+    reload,
+    util::SubscriberInitExt,
+    EnvFilter, Registry,
+};
+
+// Data
+static LOG_LEVEL_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
 
 // Function
 /**
@@ -22,104 +40,116 @@ pub fn gracefully_shutdown(message: impl Display) -> ! {
 
 /**
 Define the logger with the default configuration.
+The configuration file is loaded first, so its log level and telemetry are applied.
+The commands log to the standard error, so their output can be parsed.
 */
-pub fn setup_logger() {
+pub fn setup_logger(to_stderr: bool) {
+    // The settings load the file again and report its errors, with an invalid log level.
+    CONFIG_FILE.store(Arc::new(ConfigFile::load().0));
+    let log_level = EnvValidator::validate_log_level().unwrap_or_else(|_| EnvFilter::new("INFO"));
+    let (log_level, handle) = reload::Layer::new(log_level);
+    let _ = LOG_LEVEL_HANDLE.set(handle);
+    let (telemetry, telemetry_error) = match telemetry_layer() {
+        Ok(telemetry) => (telemetry, None),
+        Err(e) => (None, Some(e)),
+    };
+    let registry = tracing_subscriber::registry()
+        .with(log_level)
+        .with(telemetry);
+
+    let writer = match to_stderr {
+        true => BoxMakeWriter::new(std::io::stderr),
+        false => BoxMakeWriter::new(std::io::stdout),
+    };
+
     // Check if the terminal is a TTY.
-    let stdin = std::io::stdin();
-    let log_level = EnvValidator::validate_log_level();
-    if stdin.is_terminal() {
-        tracing_subscriber::fmt()
-            .with_env_filter(log_level)
-            .pretty()
+    if std::io::stdin().is_terminal() {
+        registry
+            .with(fmt::layer().pretty().with_writer(writer))
+            .init();
+    } else {
+        registry
+            .with(fmt::layer().json().with_writer(writer))
             .init();
-        return;
     }
 
-    tracing_subscriber::fmt()
-        .with_env_filter(log_level)
-        .json()
-        .init();
+    if let Some(e) = telemetry_error {
+        error!(error = e.as_str(), "Couldn't enable the telemetry.");
+    }
 }
 
 /**
-Get the PoliWarden's default identity.
+Replace the log level of the logger defined by `setup_logger`.
 */
-pub fn get_default_poliwarden_identity() -> Identity {
-    IdentityIn::new(
-        String::from("poliwarden"),
-        String::from("poliwarden"),
-        None,
-        None,
-    )
-    .into()
+pub fn set_log_level(log_level: EnvFilter) -> Result<(), String> {
+    match LOG_LEVEL_HANDLE.get() {
+        None => Err(String::from("The logger isn't initialized.")),
+        Some(handle) => handle.reload(log_level).map_err(|e| e.to_string()),
+    }
 }
 
 /**
-Get the master's default identity.
+Run a request handler in its own task, so a panic is logged and returned as an internal error (500)
+instead of crashing the process.
 */
-pub fn get_default_master_identity() -> Result<Identity, DecodeError> {
-    Ok(IdentityIn::new(
-        String::from("master"),
-        String::from("master"),
-        Some(DEFAULT_SETTINGS.bearer_header.clone()),
-        DEFAULT_SETTINGS.master_cert.clone(),
-    )
-    .into())
+pub async fn catch_panic<T: Send + 'static>(
+    handler: impl Future<Output = AppResult<T>> + Send + 'static,
+) -> AppResult<T> {
+    match tokio::spawn(handler).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => {
+            let panic = e.into_panic();
+            let message = match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+                (Some(message), _) => message.to_string(),
+                (_, Some(message)) => message.clone(),
+                (None, None) => String::from("Unknown panic."),
+            };
+            error!(panic = message.as_str(), "The request handler panicked.");
+            Err(AppError::Internal(message))
+        }
+        Err(e) => Err(AppError::Internal(e.to_string())),
+    }
 }
 
 /**
-Get the default identity policies.
+Get the PoliWarden's default identity.
 */
-pub fn get_default_identity_policies(poliw_model: &Model, master_model: &Model) -> Vec<Policy> {
-    vec![
-        PolicyIn::new(
-            Regex::new(r"^\/identities\/?$").unwrap().to_string(),
-            Regex::new(r"^(GET|POST)$").unwrap().to_string(),
-            poliw_model.get_id(),
-            master_model.get_id(),
-        )
-        .into(),
-        PolicyIn::new(
-            String::from(r"^\/identities\/\w+\/?$"),
-            Regex::new(r"^(GET|PUT|DELETE)$").unwrap().to_string(),
-            poliw_model.get_id(),
-            master_model.get_id(),
-        )
-        .into(),
-    ]
+pub fn get_default_poliwarden_identity() -> DBResult<Identity> {
+    Identity::try_from(IdentityIn::new(
+        String::from("poliwarden"),
+        String::from("poliwarden"),
+        None,
+        None,
+    ))
 }
 
 /**
-Get the default policy policies.
+Get the master's default identity, with the master bearer and certificate.
 */
-pub fn get_default_policy_policies(poliw_model: &Model, master_model: &Model) -> Vec<Policy> {
-    vec![
-        PolicyIn::new(
-            Regex::new(r"^\/policies\/?$").unwrap().to_string(),
-            Regex::new(r"^(GET|POST)$").unwrap().to_string(),
-            poliw_model.get_id(),
-            master_model.get_id(),
-        )
-        .into(),
-        PolicyIn::new(
-            String::from(r"^\/policies\/\w+\/?$"),
-            Regex::new(r"^(GET|PUT|DELETE)$").unwrap().to_string(),
-            poliw_model.get_id(),
-            master_model.get_id(),
-        )
-        .into(),
-    ]
+pub fn get_default_master_identity(master_bearer: Option<&Secret<String>>) -> DBResult<Identity> {
+    Identity::try_from(IdentityIn::new(
+        String::from("master"),
+        String::from("master"),
+        master_bearer.map(|bearer| bearer.expose().clone()),
+        DEFAULT_SETTINGS
+            .load()
+            .master_cert
+            .as_ref()
+            .map(|cert| cert.expose().clone()),
+    ))
 }
 
 /**
-Get the some environment variable. If it is not set, it will gracefully shutdown the application.
+Check if the application can write in the directory, by creating and removing a probe file.
 */
-pub fn get_env(env_name: &str) -> String {
-    match var(env_name) {
-        Ok(value) if value.is_empty() => gracefully_shutdown(format!("{env_name} must be set.")),
-        Ok(value) => value,
-        Err(_) => gracefully_shutdown(format!("{env_name} must be set.")),
-    }
+pub fn check_writable_dir(path: &Path) -> std::io::Result<()> {
+    let probe_path = path.join(".poliwarden-write-check");
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&probe_path)?;
+    remove_file(probe_path)
 }
 
 /**